@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::RwLock;
 
 use serde::de::DeserializeOwned;
 use solana_account::AccountSharedData;
@@ -15,8 +17,26 @@ pub struct AccountsDB {
     pub(crate) accounts: HashMap<Pubkey, AccountSharedData>,
     pub(crate) permanent_accounts: HashMap<Pubkey, AccountSharedData>,
     pub(crate) programs: HashMap<Pubkey, AccountSharedData>,
-    pub(crate) sysvars: HashMap<Pubkey, AccountSharedData>,
-    pub(crate) sysvar_tracker: SysvarTracker,
+    /// Behind a lock as the clock is refreshed on read.
+    pub(crate) sysvars: RwLock<HashMap<Pubkey, AccountSharedData>>,
+    pub(crate) sysvar_tracker: Mutex<SysvarTracker>,
+}
+
+impl Clone for AccountsDB {
+    fn clone(&self) -> Self {
+        Self {
+            accounts: self.accounts.clone(),
+            permanent_accounts: self.permanent_accounts.clone(),
+            programs: self.programs.clone(),
+            sysvars: RwLock::new(self.sysvars.read().expect("Sysvars lock poisoned").clone()),
+            sysvar_tracker: Mutex::new(
+                self.sysvar_tracker
+                    .lock()
+                    .expect("Sysvar tracker lock poisoned")
+                    .clone(),
+            ),
+        }
+    }
 }
 
 impl AccountsDB {
@@ -32,7 +52,11 @@ impl AccountsDB {
         self.programs.get(pubkey).map(|acc| acc.to_owned())
     }
     pub(crate) fn get_sysvar_account(&self, pubkey: &Pubkey) -> Option<AccountSharedData> {
-        self.sysvars.get(pubkey).map(|acc| acc.to_owned())
+        self.sysvars
+            .read()
+            .expect("Sysvars lock poisoned")
+            .get(pubkey)
+            .map(|acc| acc.to_owned())
     }
     pub(crate) fn get_sysvar<T: SysvarId + DeserializeOwned>(&self) -> T {
         if T::id() == Clock::id() {
@@ -60,13 +84,23 @@ impl AccountsDB {
     pub(crate) fn set_program(&mut self, pubkey: &Pubkey, account: &AccountSharedData) {
         let _ = self.programs.insert(pubkey.to_owned(), account.to_owned());
     }
+    pub(crate) fn remove_program(&mut self, pubkey: &Pubkey) {
+        let _ = self.programs.remove(pubkey);
+    }
     pub(crate) fn set_sysvar<T: Sysvar + SysvarId>(&mut self, sysvar: &T) {
         let account =
             AccountSharedData::new_data(1, &sysvar, &solana_sdk_ids::sysvar::id()).unwrap();
-        let _ = self.sysvars.insert(T::id(), account);
+        let _ = self
+            .sysvars
+            .get_mut()
+            .expect("Sysvars lock poisoned")
+            .insert(T::id(), account);
 
         if T::id() == Clock::id() {
-            self.sysvar_tracker.refresh();
+            self.sysvar_tracker
+                .get_mut()
+                .expect("Sysvar tracker lock poisoned")
+                .refresh();
         }
     }
 }
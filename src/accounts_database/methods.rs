@@ -25,14 +25,19 @@ impl AccountsDB {
             self.get_sysvar_account(pubkey)
         }
     }
+    /// Advances the clock by the time elapsed since its last update.
     pub(crate) fn update_clock(&self) {
-        let mut clock: Clock =
-            bincode::deserialize(self.sysvars.get(&Clock::id()).unwrap().data()).unwrap();
+        let mut sysvars = self.sysvars.write().expect("Sysvars lock poisoned");
+        let clock_account = sysvars.get_mut(&Clock::id()).unwrap();
+        let mut clock: Clock = bincode::deserialize(clock_account.data()).unwrap();
 
-        #[allow(mutable_transmutes)]
-        let mutable_db = unsafe { std::mem::transmute::<&AccountsDB, &mut AccountsDB>(self) };
-        mutable_db.sysvar_tracker.refresh_with_clock(&mut clock);
-        mutable_db.set_sysvar::<Clock>(&clock);
+        self.sysvar_tracker
+            .lock()
+            .expect("Sysvar tracker lock poisoned")
+            .refresh_with_clock(&mut clock);
+        clock_account
+            .serialize_data(&clock)
+            .expect("Failed to serialize the clock");
     }
 
     #[allow(dead_code)]
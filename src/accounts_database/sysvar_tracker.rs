@@ -2,9 +2,11 @@ use solana_sysvar::clock::Clock;
 
 use crate::utils::get_current_timestamp;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct SysvarTracker {
     pub last_clock_update: u64, // unix timestamp as seconds
+    /// The clock does not follow the system time, it changes only when set explicitly.
+    pub deterministic: bool,
 }
 
 impl SysvarTracker {
@@ -12,6 +14,9 @@ impl SysvarTracker {
         self.last_clock_update = get_current_timestamp();
     }
     pub fn refresh_with_clock(&mut self, clock: &mut Clock) {
+        if self.deterministic {
+            return;
+        }
         let current_timestamp = get_current_timestamp();
 
         let time_since_last_update = current_timestamp.saturating_sub(self.last_clock_update);
@@ -62,13 +67,13 @@ mod tests {
 
         // Set initial clock and get tracker time
         db.set_sysvar(&Clock::default());
-        let initial_tracker_time = db.sysvar_tracker.last_clock_update;
+        let initial_tracker_time = db.sysvar_tracker.lock().unwrap().last_clock_update;
         sleep(Duration::from_secs(1));
 
         // Force clock update
         let _: Clock = db.get_sysvar();
         assert!(
-            db.sysvar_tracker.last_clock_update > initial_tracker_time,
+            db.sysvar_tracker.lock().unwrap().last_clock_update > initial_tracker_time,
             "SysvarTracker should have been updated"
         );
     }
@@ -107,6 +112,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deterministic_clock() {
+        let mut db = AccountsDB::default();
+        db.sysvar_tracker.get_mut().unwrap().deterministic = true;
+        db.set_sysvar(&Clock::default());
+
+        sleep(Duration::from_secs(1));
+        let clock: Clock = db.get_sysvar();
+        assert_eq!(
+            clock.unix_timestamp, 0,
+            "Clock should not follow the system time"
+        );
+
+        db.forward_in_time(600);
+        let clock: Clock = db.get_sysvar();
+        assert_eq!(clock.unix_timestamp, 600);
+    }
+
     #[test]
     fn test_time_manipulation() {
         let mut db = AccountsDB::default();
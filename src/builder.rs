@@ -1,7 +1,14 @@
+use std::collections::HashSet;
+
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+
 use crate::trident_svm::TridentSVM;
 use crate::trident_svm_log::setup_cli_logging;
 use crate::trident_svm_log::setup_file_logging;
 use crate::trident_svm_log::turn_off_solana_logging;
+use crate::types::bundled_program::BundledProgram;
+use crate::types::execution_mode::ExecutionMode;
 use crate::types::trident_account::TridentAccountSharedData;
 #[cfg(feature = "syscall-v2")]
 use crate::types::trident_entrypoint::TridentEntrypoint;
@@ -13,10 +20,19 @@ pub struct TridentSVMConfig {
     syscalls_v2: bool,
     cli_logs: bool, // TODO, add better debbug levels
     debug_file_logs: Option<String>,
+    sbf_coverage: bool,
+    compute_unit_profiling: bool,
+    transaction_recording: bool,
+    loader_deployment: bool,
+    execution_mode: ExecutionMode,
     #[cfg(feature = "syscall-v2")]
     program_entrypoints: Vec<TridentEntrypoint>,
     program_binaries: Vec<TridentProgram>,
+    program_directories: Vec<String>,
+    bundled_programs: Option<Vec<BundledProgram>>,
     permanent_accounts: Vec<TridentAccountSharedData>,
+    payer: Option<Keypair>,
+    seed: Option<u64>,
 }
 
 #[derive(Default)]
@@ -52,11 +68,47 @@ impl TridentSVMBuilder {
         self
     }
 
+    /// Deploys all `.so` files of a directory such as `target/deploy`, see [`TridentProgram::from_dir`].
+    pub fn with_sbf_programs_from_dir(&mut self, path: &str) -> &Self {
+        self.config.program_directories.push(path.to_string());
+        self
+    }
+
+    /// Deploys only the selected bundled programs instead of all embedded ones.
+    ///
+    /// Bundled programs are also skipped if a program with the same id is
+    /// deployed through [`TridentSVMBuilder::with_sbf_programs`].
+    pub fn with_bundled_programs(&mut self, programs: Vec<BundledProgram>) -> &Self {
+        self.config.bundled_programs = Some(programs);
+        self
+    }
+
+    pub fn without_bundled_programs(&mut self) -> &Self {
+        self.config.bundled_programs = Some(Vec::new());
+        self
+    }
+
     pub fn with_permanent_accounts(&mut self, accounts: Vec<TridentAccountSharedData>) -> &Self {
         self.config.permanent_accounts = accounts;
         self
     }
 
+    /// Replaces the default payer, see [`TridentSVM::set_payer`].
+    pub fn with_payer(&mut self, payer: Keypair) -> &Self {
+        self.config.payer = Some(payer);
+        self
+    }
+
+    /// Derives the payer, the initial timestamp, the blockhashes and all created keypairs
+    /// from the seed, so two instances built with the same seed behave the same.
+    ///
+    /// The clock no longer follows the system time, it changes only through `set_sysvar`.
+    /// A payer set through [`TridentSVMBuilder::with_payer`] takes precedence.
+    pub fn with_seed(&mut self, seed: u64) -> &Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
     pub fn with_cli_logs(&mut self) -> &Self {
         self.config.cli_logs = true;
         self
@@ -67,8 +119,87 @@ impl TridentSVMBuilder {
         self
     }
 
+    /// Collects instruction coverage of executed SBF programs, see [`TridentSVM::get_sbf_coverage`].
+    pub fn with_sbf_coverage(&mut self) -> &Self {
+        self.config.sbf_coverage = true;
+        self
+    }
+
+    /// Profiles compute units of executed programs, see [`TridentSVM::get_compute_unit_profile`].
+    pub fn with_compute_unit_profiling(&mut self) -> &Self {
+        self.config.compute_unit_profiling = true;
+        self
+    }
+
+    /// Records the processed transactions, see [`TridentSVM::start_transaction_recording`].
+    pub fn with_transaction_recording(&mut self) -> &Self {
+        self.config.transaction_recording = true;
+        self
+    }
+
+    /// Deploys the SBF programs through transactions processed by the BPF Loader Upgradeable,
+    /// see [`TridentSVM::deploy_program_with_loader`].
+    pub fn with_loader_deployment(&mut self) -> &Self {
+        self.config.loader_deployment = true;
+        self
+    }
+
+    /// Interprets or JIT compiles the SBF programs, see [`TridentSVM::set_execution_mode`].
+    pub fn with_execution_mode(&mut self, mode: ExecutionMode) -> &Self {
+        self.config.execution_mode = mode;
+        self
+    }
+
     pub fn build(&self) -> TridentSVM {
-        let mut svm = TridentSVM::default();
+        let program_directories = self.config.program_directories.iter().map(|path| {
+            TridentProgram::from_dir(path)
+                .unwrap_or_else(|err| panic!("Failed to read programs from {path}: {err}"))
+        });
+        let programs: Vec<TridentProgram> = program_directories.flatten().collect();
+
+        // bundled programs are overridden by programs deployed under the same id
+        let overridden: HashSet<Pubkey> = self
+            .config
+            .program_binaries
+            .iter()
+            .chain(&programs)
+            .map(|program| program.program_id)
+            .collect();
+        let bundled_programs: Vec<BundledProgram> = match &self.config.bundled_programs {
+            Some(selected) => {
+                for program in selected {
+                    if program.elf().is_none() {
+                        panic!(
+                            "Bundled program {program:?} is not embedded, enable the `{}` feature",
+                            program.feature()
+                        );
+                    }
+                }
+                selected.clone()
+            }
+            None => BundledProgram::embedded().collect(),
+        };
+        let bundled_programs: Vec<BundledProgram> = bundled_programs
+            .into_iter()
+            .filter(|program| !overridden.contains(&program.program_id()))
+            .collect();
+
+        let mut svm = TridentSVM::without_programs(self.config.seed)
+            .with_solana_program_library(&bundled_programs);
+
+        if let Some(payer) = &self.config.payer {
+            svm.set_payer(payer.insecure_clone());
+        }
+
+        svm.set_execution_mode(self.config.execution_mode);
+
+        if self.config.sbf_coverage {
+            svm.enable_sbf_coverage();
+        }
+
+        if self.config.compute_unit_profiling {
+            svm.enable_compute_unit_profiling();
+        }
 
         #[cfg(feature = "syscall-v2")]
         if self.config.syscalls_v2 {
@@ -88,8 +219,17 @@ impl TridentSVMBuilder {
             svm.deploy_entrypoint_program(entry);
         }
 
-        for program in &self.config.program_binaries {
-            svm.deploy_binary_program(program);
+        for program in self.config.program_binaries.iter().chain(&programs) {
+            if self.config.loader_deployment {
+                if let Err(err) = svm.deploy_program_with_loader(program) {
+                    panic!(
+                        "Failed to deploy program {} through the loader: {err}",
+                        program.program_id
+                    );
+                }
+            } else {
+                svm.deploy_binary_program(program);
+            }
         }
 
         for account in &self.config.permanent_accounts {
@@ -97,6 +237,11 @@ impl TridentSVMBuilder {
                 .set_permanent_account(&account.address, &account.account);
         }
 
+        // the recording starts from the built state, which a replaying instance is built to
+        if self.config.transaction_recording {
+            svm.start_transaction_recording();
+        }
+
         svm
     }
 }
@@ -1,8 +1,16 @@
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
+use solana_instruction::error::InstructionError;
+use solana_program_entrypoint::deserialize;
+use solana_program_entrypoint::MAX_PERMITTED_DATA_INCREASE;
 use solana_program_runtime::invoke_context::InvokeContext;
 use solana_program_runtime::serialization::serialize_parameters;
+use solana_program_runtime::solana_sbpf::error::EbpfError;
+use solana_program_runtime::solana_sbpf::vm::EbpfVm;
+use solana_program_runtime::stable_log;
+use solana_pubkey::Pubkey;
 use solana_transaction_context::IndexOfAccount;
+use std::cell::RefCell;
 use std::collections::HashMap;
 
 use solana_sbpf::aligned_memory::AlignedMemory;
@@ -11,6 +19,88 @@ use solana_sbpf::ebpf::HOST_ALIGN;
 #[cfg(feature = "syscall-v2")]
 use trident_syscall_stubs_v2::set_invoke_context as set_invoke_context_v2;
 
+use crate::instrumentation::vm_from_ptr;
+use crate::types::trident_entrypoint::NativeEntrypoint;
+
+/// Maximum size of account data, the same as `MAX_PERMITTED_DATA_LENGTH` of `solana-system-interface`.
+const MAX_PERMITTED_DATA_LENGTH: usize = 10 * 1024 * 1024;
+
+thread_local! {
+    // Builtins are plain function pointers, so the native programs of the TridentSVM which
+    // processes the transaction are handed over through a thread local, see `EXECUTION_RECORD`.
+    static ACTIVE_NATIVE_PROGRAMS: RefCell<HashMap<Pubkey, NativeEntrypoint>> = RefCell::new(HashMap::new());
+}
+
+/// Makes the native programs available to the native builtin until the guard is dropped.
+pub(crate) struct ActiveNativePrograms {
+    previous: HashMap<Pubkey, NativeEntrypoint>,
+}
+
+impl ActiveNativePrograms {
+    pub(crate) fn install(programs: &HashMap<Pubkey, NativeEntrypoint>) -> Self {
+        Self {
+            previous: ACTIVE_NATIVE_PROGRAMS.replace(programs.clone()),
+        }
+    }
+}
+
+impl Drop for ActiveNativePrograms {
+    fn drop(&mut self) {
+        ACTIVE_NATIVE_PROGRAMS.set(std::mem::take(&mut self.previous));
+    }
+}
+
+/// Builtin entrypoint of all native programs, executes the entrypoint of the invoked program.
+pub(crate) fn native_entrypoint(
+    vm: *mut EbpfVm<InvokeContext<'static>>,
+    _arg0: u64,
+    _arg1: u64,
+    _arg2: u64,
+    _arg3: u64,
+    _arg4: u64,
+) {
+    let vm = unsafe { vm_from_ptr(vm) };
+    vm.program_result = invoke_native(vm.context_object_pointer)
+        .map(|_| 0)
+        .map_err(EbpfError::SyscallError)
+        .into();
+}
+
+fn invoke_native(invoke_context: &mut InvokeContext) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut parameter_bytes, deduplicated_indices) = pre_invocation(invoke_context)?;
+    let (program_id, account_infos, data) =
+        unsafe { deserialize(parameter_bytes.as_slice_mut().as_mut_ptr()) };
+
+    let entrypoint = ACTIVE_NATIVE_PROGRAMS
+        .with_borrow(|programs| programs.get(program_id).copied())
+        .ok_or(InstructionError::UnsupportedProgramId)?;
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        entrypoint(program_id, &account_infos, data)
+    })) {
+        Ok(Ok(())) => {}
+        Ok(Err(program_error)) => {
+            return Err(Box::new(InstructionError::from(u64::from(program_error))));
+        }
+        Err(panic) => {
+            if !panic.is::<InstructionError>() {
+                // SBF programs log the panic message as well
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or("Box<dyn Any>");
+                stable_log::program_log(
+                    &invoke_context.get_log_collector(),
+                    &format!("panicked: {message}"),
+                );
+            }
+            return Err(Box::new(panic_error(panic.as_ref())));
+        }
+    }
+
+    post_invocation(invoke_context, &account_infos, &deduplicated_indices)
+}
+
 #[macro_export]
 macro_rules! processor {
     ($builtin_function:expr) => {
@@ -103,9 +193,9 @@ macro_rules! processor {
                         return;
                     }
                 },
-                Err(_panic_error) => {
-                    ///In case of panic, set program result to ProgramFailedToComplete, log failure and return
-                    let err = $crate::processor::InstructionError::ProgramFailedToComplete;
+                Err(panic_error) => {
+                    ///In case of panic, set program result to the error of the panic, log failure and return
+                    let err = $crate::processor::panic_error(panic_error.as_ref());
                     $crate::processor::stable_log::program_failure(
                         &log_collector,
                         &program_id_,
@@ -154,6 +244,15 @@ macro_rules! processor {
     };
 }
 
+/// Returns the error a panicking native program fails with. Syscall stubs abort the program
+/// by panicking with an `InstructionError`, any other panic is `ProgramFailedToComplete`.
+pub fn panic_error(payload: &(dyn std::any::Any + Send)) -> InstructionError {
+    payload
+        .downcast_ref::<InstructionError>()
+        .cloned()
+        .unwrap_or(InstructionError::ProgramFailedToComplete)
+}
+
 pub fn pre_invocation(
     invoke_context: &mut InvokeContext,
 ) -> Result<
@@ -181,6 +280,11 @@ pub fn pre_invocation(
     Ok((parameter_bytes, deduplicated_indices))
 }
 
+/// Writes the changes of the accounts back to the transaction context, the same as the SBF
+/// loader deserializes the accounts once the program returns.
+///
+/// Changes which the program is not allowed to make, such as modifying read-only accounts or
+/// growing an account by more than `MAX_PERMITTED_DATA_INCREASE`, fail with the same error.
 pub fn post_invocation(
     invoke_context: &mut solana_program_runtime::invoke_context::InvokeContext,
     account_infos: &[crate::processor::account_info::AccountInfo<'_>],
@@ -194,29 +298,353 @@ pub fn post_invocation(
 
     let account_info_map: HashMap<_, _> = account_infos.iter().map(|a| (a.key, a)).collect();
 
-    // Commit AccountInfo changes back into KeyedAccounts
-    for i in deduplicated_indices.iter() {
+    // the accounts are checked in the order the SBF loader deserializes them
+    let mut indices: Vec<_> = deduplicated_indices.iter().copied().collect();
+    indices.sort_unstable();
+
+    for i in indices {
+        if instruction_context
+            .is_instruction_account_duplicate(i)?
+            .is_some()
+        {
+            continue;
+        }
         let mut borrowed_account =
-            instruction_context.try_borrow_instruction_account(transaction_context, *i)?;
-        if borrowed_account.is_writable() {
-            if let Some(account_info) = account_info_map.get(borrowed_account.get_key()) {
-                if borrowed_account.get_lamports() != account_info.lamports() {
-                    borrowed_account.set_lamports(account_info.lamports())?;
-                }
+            instruction_context.try_borrow_instruction_account(transaction_context, i)?;
+        let Some(account_info) = account_info_map.get(borrowed_account.get_key()) else {
+            continue;
+        };
 
-                // eprintln!("Before Setting data from Slice");
-                if borrowed_account
-                    .can_data_be_resized(account_info.data_len())
-                    .is_ok()
-                    && borrowed_account.can_data_be_changed().is_ok()
-                {
-                    borrowed_account.set_data_from_slice(&account_info.data.borrow())?;
-                }
-                if borrowed_account.get_owner() != account_info.owner {
-                    borrowed_account.set_owner(account_info.owner.as_ref())?;
+        if borrowed_account.get_lamports() != account_info.lamports() {
+            borrowed_account.set_lamports(account_info.lamports())?;
+        }
+
+        // the account infos are deserialized from the parameter bytes, which hold the original length
+        let original_data_len = unsafe { account_info.original_data_len() };
+        let data = account_info.try_borrow_data()?;
+        if data.len().saturating_sub(original_data_len) > MAX_PERMITTED_DATA_INCREASE
+            || data.len() > MAX_PERMITTED_DATA_LENGTH
+        {
+            return Err(Box::new(InstructionError::InvalidRealloc));
+        }
+        match borrowed_account.can_data_be_resized(data.len()) {
+            Ok(()) => borrowed_account.set_data_from_slice(&data)?,
+            Err(err) if borrowed_account.get_data() != *data => return Err(Box::new(err)),
+            _ => {}
+        }
+
+        // the owner is changed last, so that the lamports and data can be changed before
+        if borrowed_account.get_owner() != account_info.owner {
+            borrowed_account.set_owner(account_info.owner.as_ref())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_account::AccountSharedData;
+    use solana_account::ReadableAccount;
+    use solana_hash::Hash;
+    use solana_instruction::error::InstructionError;
+    use solana_instruction::AccountMeta;
+    use solana_instruction::Instruction;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+    use solana_sysvar::program_stubs::sol_get_return_data;
+    use solana_sysvar::program_stubs::sol_invoke_signed;
+    use solana_sysvar::program_stubs::sol_set_return_data;
+    use solana_transaction::Transaction;
+    use solana_transaction_error::TransactionError;
+
+    use crate::processor::account_info::AccountInfo;
+    use crate::processor::account_info::MAX_PERMITTED_DATA_INCREASE;
+    use crate::processor::ProgramError;
+    use crate::processor::ProgramResult;
+    use crate::trident_svm::TridentSVM;
+    use crate::types::trident_entrypoint::TridentEntrypoint;
+
+    /// Transfers lamports to the recipient through CPI, the account infos have to see the transfer.
+    fn process_instruction(
+        _program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> ProgramResult {
+        match data {
+            [0, lamports @ ..] => {
+                let balance = accounts[1].lamports();
+                // SystemInstruction::Transfer
+                let mut transfer = vec![2, 0, 0, 0];
+                transfer.extend_from_slice(lamports);
+                let instruction = Instruction::new_with_bytes(
+                    solana_sdk_ids::system_program::id(),
+                    &transfer,
+                    vec![
+                        AccountMeta::new(*accounts[0].key, true),
+                        AccountMeta::new(*accounts[1].key, false),
+                    ],
+                );
+                sol_invoke_signed(&instruction, accounts, &[])?;
+                let expected = balance + u64::from_le_bytes(lamports.try_into().unwrap());
+                if accounts[1].lamports() != expected {
+                    return Err(ProgramError::Custom(4));
                 }
+                Ok(())
             }
+            [1] => Err(ProgramError::Custom(3)),
+            _ => panic!("unknown instruction"),
         }
     }
-    Ok(())
+
+    /// Returns the instruction data, or more return data than allowed for `[0xff]`.
+    fn return_data_callee(
+        _program_id: &Pubkey,
+        _accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> ProgramResult {
+        match data {
+            [0xff] => sol_set_return_data(&[0; 1025]),
+            _ => sol_set_return_data(data),
+        }
+        Ok(())
+    }
+
+    /// Invokes the callee with the instruction data and returns whether the callee
+    /// returned data, followed by the data.
+    fn return_data_caller(
+        _program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> ProgramResult {
+        let callee = *accounts[0].key;
+        sol_invoke_signed(
+            &Instruction::new_with_bytes(callee, data, vec![]),
+            accounts,
+            &[],
+        )?;
+        match sol_get_return_data() {
+            Some((program_id, return_data)) => {
+                assert_eq!(program_id, callee);
+                sol_set_return_data(&[&[1], return_data.as_slice()].concat());
+            }
+            None => sol_set_return_data(&[0]),
+        }
+        Ok(())
+    }
+
+    /// Changes the first account, the instruction selects the change.
+    fn account_changes(
+        _program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> ProgramResult {
+        let account = &accounts[0];
+        match data {
+            [0] => {
+                account.resize(account.data_len() + MAX_PERMITTED_DATA_INCREASE)?;
+                account.try_borrow_mut_data()?.fill(1);
+            }
+            [1] => account.try_borrow_mut_data()?[0] = 1,
+            // grows the data beyond the limit without the checks of `resize`
+            [2] => {
+                let data = vec![0; account.data_len() + MAX_PERMITTED_DATA_INCREASE + 1];
+                *account.try_borrow_mut_data()? = Vec::leak(data);
+            }
+            _ => **account.try_borrow_mut_lamports()? -= 1,
+        }
+        Ok(())
+    }
+
+    fn process(svm: &mut TridentSVM, program_id: &Pubkey, data: &[u8]) -> (Pubkey, Transaction) {
+        let payer = svm.get_payer();
+        let recipient = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(
+            *program_id,
+            data,
+            vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(solana_sdk_ids::system_program::id(), false),
+            ],
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        (recipient, transaction)
+    }
+
+    #[test]
+    fn test_native_entrypoint() {
+        let program_id = Pubkey::new_unique();
+        let mut svm = TridentSVM::builder().with_syscalls_v2().build();
+        svm.deploy_entrypoint_program(&TridentEntrypoint::native(
+            program_id,
+            None,
+            process_instruction,
+        ));
+
+        let mut data = vec![0];
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        let (recipient, transaction) = process(&mut svm, &program_id, &data);
+        let result = svm.process_transaction_with_settle(transaction);
+        let executed = result.get_result().processing_results[0]
+            .as_ref()
+            .unwrap()
+            .executed_transaction()
+            .unwrap();
+        // the account info sees the transfer made through CPI
+        assert_eq!(executed.execution_details.status, Ok(()));
+        assert_eq!(svm.get_account(&recipient).unwrap().lamports(), 1_000_000);
+
+        let (_, transaction) = process(&mut svm, &program_id, &[1]);
+        let result = svm.process_transaction_with_settle(transaction);
+        assert_eq!(
+            result.get_result().processing_results[0].flattened_result(),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(3)
+            ))
+        );
+
+        let (_, transaction) = process(&mut svm, &program_id, &[2]);
+        let result = svm.process_transaction_with_settle(transaction);
+        let executed = result.get_result().processing_results[0]
+            .as_ref()
+            .unwrap()
+            .executed_transaction()
+            .unwrap();
+        assert_eq!(
+            executed.execution_details.status,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::ProgramFailedToComplete
+            ))
+        );
+        assert!(executed
+            .execution_details
+            .log_messages
+            .as_ref()
+            .unwrap()
+            .contains(&"Program log: panicked: unknown instruction".to_string()));
+    }
+
+    #[test]
+    fn test_native_return_data() {
+        let caller = Pubkey::new_unique();
+        let callee = Pubkey::new_unique();
+        let mut svm = TridentSVM::builder().with_syscalls_v2().build();
+        svm.deploy_entrypoint_program(&TridentEntrypoint::native(caller, None, return_data_caller));
+        svm.deploy_entrypoint_program(&TridentEntrypoint::native(callee, None, return_data_callee));
+
+        let mut invoke = |program_id: &Pubkey, data: &[u8]| {
+            let payer = svm.get_payer();
+            let instruction = Instruction::new_with_bytes(
+                *program_id,
+                data,
+                vec![AccountMeta::new_readonly(callee, false)],
+            );
+            let transaction = Transaction::new_signed_with_payer(
+                &[instruction],
+                Some(&payer.pubkey()),
+                &[&payer],
+                Hash::default(),
+            );
+            let result = svm.process_transaction_with_settle(transaction);
+            let executed = result.get_result().processing_results[0]
+                .as_ref()
+                .unwrap()
+                .executed_transaction()
+                .unwrap();
+            executed
+                .execution_details
+                .status
+                .clone()
+                .map(|_| executed.execution_details.return_data.clone())
+        };
+
+        // return data of the top level instruction
+        let return_data = invoke(&callee, &[5]).unwrap().unwrap();
+        assert_eq!(return_data.program_id, callee);
+        assert_eq!(return_data.data, vec![5]);
+
+        let return_data = invoke(&caller, &[7, 8]).unwrap().unwrap();
+        assert_eq!(return_data.program_id, caller);
+        assert_eq!(return_data.data, vec![1, 7, 8]);
+
+        // empty return data is not visible to the caller
+        let return_data = invoke(&caller, &[]).unwrap().unwrap();
+        assert_eq!(return_data.data, vec![0]);
+
+        assert_eq!(
+            invoke(&callee, &[0xff]),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::ProgramFailedToComplete
+            ))
+        );
+    }
+
+    #[test]
+    fn test_native_account_changes() {
+        let program_id = Pubkey::new_unique();
+        let mut svm = TridentSVM::builder().with_syscalls_v2().build();
+        svm.deploy_entrypoint_program(&TridentEntrypoint::native(
+            program_id,
+            None,
+            account_changes,
+        ));
+
+        let mut change = |owner: &Pubkey, is_writable: bool, data: &[u8]| {
+            let address = Pubkey::new_unique();
+            svm.set_account(
+                &address,
+                &AccountSharedData::new(1_000_000_000, 10, owner),
+                false,
+            );
+            let payer = svm.get_payer();
+            let account_meta = match is_writable {
+                true => AccountMeta::new(address, false),
+                false => AccountMeta::new_readonly(address, false),
+            };
+            let instruction = Instruction::new_with_bytes(program_id, data, vec![account_meta]);
+            let transaction = Transaction::new_signed_with_payer(
+                &[instruction],
+                Some(&payer.pubkey()),
+                &[&payer],
+                Hash::default(),
+            );
+            let result = svm.process_transaction_with_settle(transaction);
+            result.get_result().processing_results[0]
+                .flattened_result()
+                .map(|_| svm.get_account(&address).unwrap())
+        };
+        let error = |err| Err(TransactionError::InstructionError(0, err));
+
+        let account = change(&program_id, true, &[0]).unwrap();
+        assert_eq!(account.data(), [1; 10 + MAX_PERMITTED_DATA_INCREASE]);
+        assert_eq!(
+            change(&program_id, false, &[1]),
+            error(InstructionError::ReadonlyDataModified)
+        );
+        assert_eq!(
+            change(&Pubkey::new_unique(), true, &[1]),
+            error(InstructionError::ExternalAccountDataModified)
+        );
+        assert_eq!(
+            change(&program_id, true, &[2]),
+            error(InstructionError::InvalidRealloc)
+        );
+        assert_eq!(
+            change(&program_id, false, &[3]),
+            error(InstructionError::ReadonlyLamportChange)
+        );
+        assert_eq!(
+            change(&Pubkey::new_unique(), true, &[3]),
+            error(InstructionError::ExternalAccountLamportSpend)
+        );
+    }
 }
@@ -0,0 +1,173 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use object::Object;
+use object::ObjectSection;
+use object::ObjectSymbol;
+use object::SymbolKind;
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_program_runtime::loaded_programs::ProgramRuntimeEnvironment;
+use solana_program_runtime::solana_sbpf::ebpf;
+use solana_program_runtime::solana_sbpf::elf::Executable;
+use solana_program_runtime::solana_sbpf::static_analysis::Analysis;
+
+use crate::types::sbf_coverage::SourceLocation;
+
+type DwarfReader = gimli::EndianRcSlice<gimli::RunTimeEndian>;
+
+/// Size of a single SBF instruction slot in bytes.
+const INSTRUCTION_SIZE: u64 = 8;
+
+/// Debug information of an SBF program ELF, used to map program counters to source lines.
+pub(crate) struct SbfDebugInfo {
+    text_address: u64,
+    text_size: u64,
+    context: addr2line::Context<DwarfReader>,
+}
+
+impl SbfDebugInfo {
+    /// Parses the DWARF sections of the ELF, returns `None` if the ELF
+    /// cannot be parsed or does not contain debug information.
+    pub(crate) fn parse(elf: &[u8]) -> Option<Self> {
+        let object = object::File::parse(elf).ok()?;
+        let text = object.section_by_name(".text")?;
+        let text_address = text.address();
+        let text_size = text.size();
+
+        object.section_by_name(".debug_info")?;
+
+        let endian = if object.is_little_endian() {
+            gimli::RunTimeEndian::Little
+        } else {
+            gimli::RunTimeEndian::Big
+        };
+        let dwarf = gimli::Dwarf::load(|id| -> Result<DwarfReader, gimli::Error> {
+            let data = object
+                .section_by_name(id.name())
+                .and_then(|section| section.uncompressed_data().ok())
+                .unwrap_or(Cow::Borrowed(&[]));
+            Ok(gimli::EndianRcSlice::new(Rc::from(&*data), endian))
+        })
+        .ok()?;
+        let context = addr2line::Context::from_dwarf(dwarf).ok()?;
+
+        Some(Self {
+            text_address,
+            text_size,
+            context,
+        })
+    }
+
+    /// Virtual address of the instruction at the given program counter.
+    pub(crate) fn address_of(&self, pc: u64) -> u64 {
+        self.text_address
+            .saturating_add(pc.saturating_mul(INSTRUCTION_SIZE))
+    }
+
+    pub(crate) fn locate(&self, pc: u64) -> Option<SourceLocation> {
+        let location = self.context.find_location(self.address_of(pc)).ok()??;
+        Some(SourceLocation {
+            file: location.file?.to_string(),
+            line: location.line?,
+        })
+    }
+
+    /// All source lines which have instructions in the `.text` section.
+    pub(crate) fn instrumented_lines(&self) -> BTreeSet<SourceLocation> {
+        let text_end = self.text_address.saturating_add(self.text_size);
+        let Ok(locations) = self
+            .context
+            .find_location_range(self.text_address, text_end)
+        else {
+            return BTreeSet::new();
+        };
+        locations
+            .filter_map(|(_address, _size, location)| {
+                Some(SourceLocation {
+                    file: location.file?.to_string(),
+                    line: location.line?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Opcodes and function symbols of an SBF program, used to rebuild call stacks from traces.
+pub(crate) struct SbfCallGraph {
+    opcodes: Vec<u8>,
+    functions: HashMap<u64, String>,
+}
+
+impl SbfCallGraph {
+    pub(crate) fn parse(elf: &[u8]) -> Option<Self> {
+        let object = object::File::parse(elf).ok()?;
+        let text = object.section_by_name(".text")?;
+        let text_address = text.address();
+        let opcodes = text
+            .data()
+            .ok()?
+            .chunks(INSTRUCTION_SIZE as usize)
+            .map(|instruction| instruction[0])
+            .collect();
+
+        let functions = object
+            .symbols()
+            .chain(object.dynamic_symbols())
+            .filter(|symbol| symbol.kind() == SymbolKind::Text && symbol.address() >= text_address)
+            .filter_map(|symbol| {
+                let name = symbol.name().ok().filter(|name| !name.is_empty())?;
+                let pc = (symbol.address() - text_address) / INSTRUCTION_SIZE;
+                // `;` separates the frames of folded stacks
+                let name = addr2line::demangle_auto(Cow::Borrowed(name), None).replace(';', ":");
+                Some((pc, name))
+            })
+            .collect();
+
+        Some(Self { opcodes, functions })
+    }
+
+    pub(crate) fn is_call(&self, pc: u64) -> bool {
+        matches!(
+            self.opcodes.get(pc as usize),
+            Some(&(ebpf::CALL_IMM | ebpf::CALL_REG))
+        )
+    }
+
+    pub(crate) fn is_return(&self, pc: u64) -> bool {
+        matches!(
+            self.opcodes.get(pc as usize),
+            Some(&(ebpf::EXIT | ebpf::RETURN))
+        )
+    }
+
+    /// Name of the function starting at the given program counter.
+    pub(crate) fn function_name(&self, pc: u64) -> String {
+        self.functions
+            .get(&pc)
+            .cloned()
+            .unwrap_or_else(|| format!("function_{pc}"))
+    }
+}
+
+/// Disassembles the program, returns the program counter and assembly of every instruction.
+pub(crate) fn disassemble(
+    elf: &[u8],
+    environment: ProgramRuntimeEnvironment,
+) -> Option<Vec<(u64, String)>> {
+    let executable = Executable::<InvokeContext<'static>>::load(elf, environment).ok()?;
+    let analysis = Analysis::from_executable(&executable).ok()?;
+    Some(
+        analysis
+            .instructions
+            .iter()
+            .map(|insn| {
+                (
+                    insn.ptr as u64,
+                    analysis.disassemble_instruction(insn, insn.ptr),
+                )
+            })
+            .collect(),
+    )
+}
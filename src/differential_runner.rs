@@ -0,0 +1,388 @@
+use solana_account::AccountSharedData;
+use solana_pubkey::Pubkey;
+use solana_svm::transaction_processing_result::ProcessedTransaction;
+use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+use solana_transaction::Transaction;
+use solana_transaction_error::TransactionError;
+
+use crate::trident_svm::TridentSVM;
+use crate::types::execution_difference::DifferenceRule;
+use crate::types::execution_difference::DifferentialReport;
+use crate::types::execution_difference::ExecutionDifference;
+use crate::types::transaction_result::TridentTransactionProcessingResult;
+use crate::types::trident_program::TridentProgram;
+
+/// Executes every transaction by two instances and reports how the executions differ,
+/// such as an instance with native programs and one with the SBF builds of the same programs.
+///
+/// Both instances are expected to start from the same state. Compute units differ between
+/// native and SBF programs, so the logs of consumed compute units are not compared, and
+/// instances built from the same seed also share the clock.
+pub struct DifferentialRunner {
+    reference: TridentSVM,
+    candidate: TridentSVM,
+    allowed_differences: Vec<Box<DifferenceRule>>,
+}
+
+/// The outcome of a transaction which is compared between the instances.
+struct Execution {
+    result: Result<(), TransactionError>,
+    logs: Vec<String>,
+    return_data: Option<(Pubkey, Vec<u8>)>,
+}
+
+impl DifferentialRunner {
+    /// The transactions are paid by the payer of the reference instance, which also
+    /// becomes the payer of the candidate.
+    pub fn new(reference: TridentSVM, mut candidate: TridentSVM) -> Self {
+        candidate.set_payer(reference.get_payer());
+        Self {
+            reference,
+            candidate,
+            allowed_differences: Vec::new(),
+        }
+    }
+
+    /// Compares two versions of a program, each deployed to a fork of the instance.
+    ///
+    /// The transactions see the same state, except for the program itself, so the reported
+    /// differences show how the upgrade to the candidate version changes the behavior.
+    pub fn for_program_versions(
+        svm: &TridentSVM,
+        reference: &TridentProgram,
+        candidate: &TridentProgram,
+    ) -> Self {
+        assert_eq!(
+            reference.program_id, candidate.program_id,
+            "Program versions have to be deployed to the same program id"
+        );
+
+        let mut reference_svm = svm.fork();
+        reference_svm.deploy_binary_program(reference);
+        let mut candidate_svm = svm.fork();
+        candidate_svm.deploy_binary_program(candidate);
+        Self::new(reference_svm, candidate_svm)
+    }
+
+    /// Differences for which the rule returns `true` are expected and not reported.
+    pub fn allow_difference<F>(&mut self, rule: F)
+    where
+        F: Fn(&ExecutionDifference) -> bool + Send + Sync + 'static,
+    {
+        self.allowed_differences.push(Box::new(rule));
+    }
+
+    pub fn get_reference(&self) -> &TridentSVM {
+        &self.reference
+    }
+
+    pub fn get_reference_mut(&mut self) -> &mut TridentSVM {
+        &mut self.reference
+    }
+
+    pub fn get_candidate(&self) -> &TridentSVM {
+        &self.candidate
+    }
+
+    pub fn get_candidate_mut(&mut self) -> &mut TridentSVM {
+        &mut self.candidate
+    }
+
+    /// Sets the account in both instances.
+    pub fn set_account(&mut self, pubkey: &Pubkey, account: &AccountSharedData, permanent: bool) {
+        self.reference.set_account(pubkey, account, permanent);
+        self.candidate.set_account(pubkey, account, permanent);
+    }
+
+    /// Processes the transaction by both instances and compares the results, logs,
+    /// return data and writable accounts of the executions. Allowed differences are
+    /// left out of the report.
+    pub fn process_transaction(&mut self, transaction: Transaction) -> DifferentialReport {
+        let message = &transaction.message;
+        let writable_accounts: Vec<Pubkey> = (0..message.account_keys.len())
+            .filter(|index| message.is_maybe_writable(*index, None))
+            .map(|index| message.account_keys[index])
+            .collect();
+
+        let reference = self
+            .reference
+            .process_transaction_with_settle(transaction.clone());
+        let candidate = self.candidate.process_transaction_with_settle(transaction);
+
+        let mut differences = Vec::new();
+        let reference_execution = Execution::new(&reference);
+        let candidate_execution = Execution::new(&candidate);
+        if reference_execution.result != candidate_execution.result {
+            differences.push(ExecutionDifference::Result {
+                reference: reference_execution.result,
+                candidate: candidate_execution.result,
+            });
+        }
+        if reference_execution.logs != candidate_execution.logs {
+            differences.push(ExecutionDifference::Logs {
+                reference: reference_execution.logs,
+                candidate: candidate_execution.logs,
+            });
+        }
+        if reference_execution.return_data != candidate_execution.return_data {
+            differences.push(ExecutionDifference::ReturnData {
+                reference: reference_execution.return_data,
+                candidate: candidate_execution.return_data,
+            });
+        }
+        for address in writable_accounts {
+            let reference_account = self.reference.get_account(&address);
+            let candidate_account = self.candidate.get_account(&address);
+            if reference_account != candidate_account {
+                differences.push(ExecutionDifference::Account {
+                    address,
+                    reference: reference_account,
+                    candidate: candidate_account,
+                });
+            }
+        }
+
+        differences.retain(|difference| {
+            !self
+                .allowed_differences
+                .iter()
+                .any(|is_allowed| is_allowed(difference))
+        });
+
+        DifferentialReport {
+            reference,
+            candidate,
+            differences,
+        }
+    }
+
+    /// Processes the transactions in order and returns the reports of the transactions
+    /// whose executions differ, together with their index in the sequence.
+    pub fn replay<I>(&mut self, transactions: I) -> Vec<(usize, DifferentialReport)>
+    where
+        I: IntoIterator<Item = Transaction>,
+    {
+        transactions
+            .into_iter()
+            .enumerate()
+            .map(|(index, transaction)| (index, self.process_transaction(transaction)))
+            .filter(|(_, report)| !report.is_equivalent())
+            .collect()
+    }
+}
+
+impl Execution {
+    fn new(result: &TridentTransactionProcessingResult) -> Self {
+        let processing_result = &result.get_result().processing_results[0];
+        let Ok(ProcessedTransaction::Executed(executed)) = processing_result else {
+            return Self {
+                result: processing_result.flattened_result(),
+                logs: Vec::new(),
+                return_data: None,
+            };
+        };
+
+        let details = &executed.execution_details;
+        Self {
+            result: details.status.clone(),
+            logs: details
+                .log_messages
+                .iter()
+                .flatten()
+                .filter(|log| !is_compute_units_log(log))
+                .cloned()
+                .collect(),
+            return_data: details
+                .return_data
+                .as_ref()
+                .map(|return_data| (return_data.program_id, return_data.data.clone())),
+        }
+    }
+}
+
+/// Whether the log is the `Program <id> consumed <units> of <limit> compute units`
+/// log of an SBF program.
+fn is_compute_units_log(log: &str) -> bool {
+    log.starts_with("Program ") && log.contains(" consumed ") && log.ends_with(" compute units")
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_account::AccountSharedData;
+    use solana_hash::Hash;
+    use solana_instruction::error::InstructionError;
+    use solana_instruction::AccountMeta;
+    use solana_instruction::Instruction;
+    use solana_keypair::Keypair;
+    use solana_pubkey::Pubkey;
+    use solana_rent::Rent;
+    use solana_signer::Signer;
+    use solana_transaction::Transaction;
+    use solana_transaction_error::TransactionError;
+
+    use crate::differential_runner::DifferentialRunner;
+    use crate::test_utils::initialize_mint_transaction;
+    use crate::trident_svm::TridentSVM;
+    use crate::types::execution_difference::ExecutionDifference;
+    use crate::types::trident_program::TridentProgram;
+
+    fn token_instruction(
+        svm: &TridentSVM,
+        program_id: &Pubkey,
+        data: &[u8],
+        account: &Pubkey,
+    ) -> Transaction {
+        let payer = svm.get_payer();
+        Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                *program_id,
+                data,
+                vec![AccountMeta::new(*account, false)],
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        )
+    }
+
+    #[test]
+    fn test_program_versions() {
+        let program_id = Pubkey::new_unique();
+        let mut svm = TridentSVM::builder().without_bundled_programs().build();
+        let (_, initialize_mint) = initialize_mint_transaction(&mut svm, &program_id);
+        // mint with space for the MintCloseAuthority extension, only supported by Token 2022
+        let extended_mint = Keypair::new().pubkey();
+        let account =
+            AccountSharedData::new(Rent::default().minimum_balance(234), 234, &program_id);
+        svm.set_account(&extended_mint, &account, false);
+
+        let mut runner = DifferentialRunner::for_program_versions(
+            &svm,
+            &TridentProgram::new(
+                program_id,
+                None,
+                include_bytes!("solana-program-library/spl-token-mainnet.so").to_vec(),
+            ),
+            &TridentProgram::new(
+                program_id,
+                None,
+                include_bytes!("solana-program-library/spl-2022-token-mainnet.so").to_vec(),
+            ),
+        );
+        runner
+            .allow_difference(|difference| matches!(difference, ExecutionDifference::Logs { .. }));
+
+        let transactions = vec![
+            initialize_mint,
+            // InitializeMintCloseAuthority without a close authority
+            token_instruction(&svm, &program_id, &[25, 0], &extended_mint),
+        ];
+        let reports = runner.replay(transactions);
+
+        assert_eq!(reports.len(), 1);
+        let (index, report) = &reports[0];
+        assert_eq!(*index, 1);
+        let differences = report.get_differences();
+        assert_eq!(differences.len(), 2);
+        assert_eq!(
+            differences[0],
+            ExecutionDifference::Result {
+                // TokenError::InvalidInstruction
+                reference: Err(TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(12)
+                )),
+                candidate: Ok(()),
+            }
+        );
+        assert!(matches!(
+            &differences[1],
+            ExecutionDifference::Account { address, .. } if *address == extended_mint
+        ));
+        // the program is deployed to the forks only
+        assert!(svm.get_account(&program_id).is_none());
+    }
+}
+
+#[cfg(all(test, feature = "syscall-v2", feature = "spl-memo"))]
+mod native_tests {
+    use solana_hash::Hash;
+    use solana_instruction::error::InstructionError;
+    use solana_instruction::Instruction;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+    use solana_sysvar::program_stubs::sol_log;
+    use solana_transaction::Transaction;
+    use solana_transaction_error::TransactionError;
+
+    use crate::differential_runner::DifferentialRunner;
+    use crate::processor::account_info::AccountInfo;
+    use crate::processor::ProgramResult;
+    use crate::trident_svm::TridentSVM;
+    use crate::types::bundled_program::BundledProgram;
+    use crate::types::execution_difference::ExecutionDifference;
+    use crate::types::trident_entrypoint::TridentEntrypoint;
+
+    /// Native build of the memo program which accepts invalid UTF-8.
+    fn native_memo(_program_id: &Pubkey, _accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let memo = String::from_utf8_lossy(data);
+        sol_log(&format!("Memo (len {}): {:?}", memo.len(), memo));
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_and_sbf_differences() {
+        let memo_program = BundledProgram::Memo.program_id();
+        let mut builder = TridentSVM::builder();
+        builder.with_bundled_programs(vec![BundledProgram::Memo]);
+        let reference = builder.build();
+
+        let mut builder = TridentSVM::builder();
+        builder.without_bundled_programs();
+        builder.with_syscalls_v2();
+        builder.with_program_entries(vec![TridentEntrypoint::native(
+            memo_program,
+            None,
+            native_memo,
+        )]);
+        let candidate = builder.build();
+
+        let mut runner = DifferentialRunner::new(reference, candidate);
+        let mut memo = |data: &[u8]| {
+            let payer = runner.get_reference().get_payer();
+            let transaction = Transaction::new_signed_with_payer(
+                &[Instruction::new_with_bytes(memo_program, data, vec![])],
+                Some(&payer.pubkey()),
+                &[&payer],
+                Hash::default(),
+            );
+            runner.process_transaction(transaction)
+        };
+
+        let report = memo(b"hello");
+        assert!(report.is_equivalent(), "{:?}", report.get_differences());
+
+        let report = memo(&[0xff]);
+        let differences = report.get_differences();
+        assert_eq!(differences.len(), 2);
+        assert_eq!(
+            differences[0],
+            ExecutionDifference::Result {
+                reference: Err(TransactionError::InstructionError(
+                    0,
+                    InstructionError::InvalidInstructionData
+                )),
+                candidate: Ok(()),
+            }
+        );
+        let ExecutionDifference::Logs {
+            reference,
+            candidate,
+        } = &differences[1]
+        else {
+            panic!("the logs should differ");
+        };
+        assert!(reference.contains(&"Program log: Invalid UTF-8, from byte 0".to_string()));
+        assert!(candidate.contains(&"Program log: Memo (len 3): \"\u{fffd}\"".to_string()));
+    }
+}
@@ -0,0 +1,57 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use solana_instruction::error::InstructionError;
+
+use crate::types::fault_injection::SyscallFault;
+
+/// Syscall faults of the transaction being processed and the calls of every syscall so far.
+#[derive(Default)]
+struct SyscallFaults {
+    faults: Vec<SyscallFault>,
+    calls: HashMap<&'static str, u64>,
+}
+
+thread_local! {
+    // Syscalls are plain function pointers, so the faults are handed over the same way as
+    // the execution records, see `EXECUTION_RECORD`.
+    static ACTIVE_SYSCALL_FAULTS: RefCell<SyscallFaults> = RefCell::new(SyscallFaults::default());
+}
+
+/// Makes the syscall faults available to the syscalls until the guard is dropped,
+/// the calls are counted from the installation.
+pub(crate) struct ActiveSyscallFaults {
+    previous: SyscallFaults,
+}
+
+impl ActiveSyscallFaults {
+    pub(crate) fn install(faults: &[SyscallFault]) -> Self {
+        let faults = SyscallFaults {
+            faults: faults.to_vec(),
+            calls: HashMap::new(),
+        };
+        Self {
+            previous: ACTIVE_SYSCALL_FAULTS.replace(faults),
+        }
+    }
+}
+
+impl Drop for ActiveSyscallFaults {
+    fn drop(&mut self) {
+        ACTIVE_SYSCALL_FAULTS.set(std::mem::take(&mut self.previous));
+    }
+}
+
+/// Counts the call of the syscall and returns the error it fails with, if any.
+pub(crate) fn syscall_fault(name: &'static str) -> Option<InstructionError> {
+    ACTIVE_SYSCALL_FAULTS.with_borrow_mut(|active| {
+        let calls = active.calls.entry(name).or_default();
+        let call = *calls;
+        *calls += 1;
+        active
+            .faults
+            .iter()
+            .find(|fault| fault.name == name && fault.call == call)
+            .map(|fault| fault.error.clone())
+    })
+}
@@ -0,0 +1,79 @@
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_program_runtime::solana_sbpf::declare_builtin_function;
+use solana_program_runtime::solana_sbpf::memory_region::MemoryMapping;
+
+use super::enter_invocation;
+use super::exit_invocation;
+
+/// Index of the program counter within a register trace entry.
+const TRACE_PC_INDEX: usize = 11;
+
+declare_builtin_function!(
+    /// SBF loader entrypoint which forwards to the original loader and records
+    /// the consumed compute units and the instruction trace of the executed program.
+    InstrumentedLoaderEntrypoint,
+    fn rust(
+        invoke_context: &mut InvokeContext,
+        arg0: u64,
+        arg1: u64,
+        arg2: u64,
+        arg3: u64,
+        arg4: u64,
+        memory_mapping: &mut MemoryMapping,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let (program_id, is_loader_v4) = {
+            let transaction_context = &invoke_context.transaction_context;
+            let instruction_context = transaction_context.get_current_instruction_context()?;
+            let program_id = *instruction_context.get_last_program_key(transaction_context)?;
+            let program_account =
+                instruction_context.try_borrow_last_program_account(transaction_context)?;
+            (
+                program_id,
+                solana_sdk_ids::loader_v4::check_id(&program_id)
+                    || solana_sdk_ids::loader_v4::check_id(program_account.get_owner()),
+            )
+        };
+
+        enter_invocation(invoke_context, program_id);
+
+        let result = if is_loader_v4 {
+            solana_loader_v4_program::Entrypoint::rust(
+                invoke_context,
+                arg0,
+                arg1,
+                arg2,
+                arg3,
+                arg4,
+                memory_mapping,
+            )
+        } else {
+            solana_bpf_loader_program::Entrypoint::rust(
+                invoke_context,
+                arg0,
+                arg1,
+                arg2,
+                arg3,
+                arg4,
+                memory_mapping,
+            )
+        };
+
+        // The syscall context of the current frame is only populated if a VM was created,
+        // loader management instructions do not produce any trace.
+        let pcs: Vec<u64> = match invoke_context.syscall_context.last_mut() {
+            Some(Some(syscall_context)) => std::mem::take(&mut syscall_context.trace_log)
+                .into_iter()
+                .map(|state| state[TRACE_PC_INDEX])
+                .collect(),
+            _ => Vec::new(),
+        };
+        // the executable tells which of the traced instructions transfer control
+        let program = match pcs.is_empty() {
+            true => None,
+            false => invoke_context.program_cache_for_tx_batch.find(&program_id),
+        };
+        exit_invocation(invoke_context, pcs, program);
+
+        result
+    }
+);
@@ -0,0 +1,166 @@
+pub(crate) mod loader;
+#[cfg(feature = "syscall-v2")]
+pub(crate) mod native;
+pub(crate) mod profiler;
+pub(crate) mod syscalls;
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_program_runtime::loaded_programs::ProgramCacheEntry;
+use solana_program_runtime::solana_sbpf::vm::get_runtime_environment_key;
+use solana_program_runtime::solana_sbpf::vm::ContextObject;
+use solana_program_runtime::solana_sbpf::vm::EbpfVm;
+use solana_pubkey::Pubkey;
+
+/// A program invocation executed within a transaction.
+pub(crate) struct InvocationRecord {
+    pub(crate) program_id: Pubkey,
+    /// Index of the invoking record, `None` for top level instructions.
+    pub(crate) parent: Option<usize>,
+    /// Number of instructions the parent executed before it invoked this program.
+    pub(crate) parent_trace_position: usize,
+    /// Compute units consumed including nested invocations.
+    pub(crate) units: u64,
+    /// Compute units consumed excluding nested invocations.
+    pub(crate) self_units: u64,
+    /// Executed program counters in execution order, empty if the program was not traced.
+    pub(crate) pcs: Vec<u64>,
+    /// The executed program, `None` if the program was not traced.
+    pub(crate) program: Option<Arc<ProgramCacheEntry>>,
+}
+
+/// A syscall invoked by an SBF program.
+pub(crate) struct SyscallRecord {
+    /// Index of the invoking record.
+    pub(crate) invocation: usize,
+    /// Number of instructions the program executed before the syscall.
+    pub(crate) trace_position: usize,
+    pub(crate) name: &'static str,
+    /// Compute units consumed excluding nested invocations.
+    pub(crate) units: u64,
+}
+
+/// Everything recorded by the instrumented entrypoints during one transaction.
+#[derive(Default)]
+pub(crate) struct ExecutionRecord {
+    pub(crate) invocations: Vec<InvocationRecord>,
+    pub(crate) syscalls: Vec<SyscallRecord>,
+    frames: Vec<Frame>,
+}
+
+struct Frame {
+    invocation: usize,
+    remaining_units: u64,
+    nested_units: u64,
+}
+
+thread_local! {
+    // Entrypoints and syscalls are plain function pointers, so the records are handed over
+    // to the owning TridentSVM through a thread local. Transactions are executed
+    // synchronously on the calling thread, which keeps instances on separate threads isolated.
+    static EXECUTION_RECORD: RefCell<ExecutionRecord> = RefCell::new(ExecutionRecord::default());
+}
+
+pub(crate) fn take_execution_record() -> ExecutionRecord {
+    EXECUTION_RECORD.with_borrow_mut(std::mem::take)
+}
+
+/// Opens a record for the program which is about to be invoked.
+pub(crate) fn enter_invocation(invoke_context: &InvokeContext, program_id: Pubkey) {
+    // The syscall context of the caller is one level below the (empty) context of the callee.
+    let parent_trace_position = invoke_context
+        .syscall_context
+        .iter()
+        .rev()
+        .nth(1)
+        .and_then(|syscall_context| syscall_context.as_ref())
+        .map(|syscall_context| syscall_context.trace_log.len())
+        .unwrap_or_default();
+
+    EXECUTION_RECORD.with_borrow_mut(|record| {
+        let invocation = record.invocations.len();
+        record.invocations.push(InvocationRecord {
+            program_id,
+            parent: record.frames.last().map(|frame| frame.invocation),
+            parent_trace_position,
+            units: 0,
+            self_units: 0,
+            pcs: Vec::new(),
+            program: None,
+        });
+        record.frames.push(Frame {
+            invocation,
+            remaining_units: invoke_context.get_remaining(),
+            nested_units: 0,
+        });
+    });
+}
+
+/// Closes the record of the innermost invocation.
+pub(crate) fn exit_invocation(
+    invoke_context: &InvokeContext,
+    pcs: Vec<u64>,
+    program: Option<Arc<ProgramCacheEntry>>,
+) {
+    EXECUTION_RECORD.with_borrow_mut(|record| {
+        let Some(frame) = record.frames.pop() else {
+            return;
+        };
+        let units = frame
+            .remaining_units
+            .saturating_sub(invoke_context.get_remaining());
+        if let Some(parent) = record.frames.last_mut() {
+            parent.nested_units = parent.nested_units.saturating_add(units);
+        }
+
+        let invocation = &mut record.invocations[frame.invocation];
+        invocation.units = units;
+        invocation.self_units = units.saturating_sub(frame.nested_units);
+        invocation.pcs = pcs;
+        invocation.program = program;
+    });
+}
+
+/// Compute units consumed so far by invocations nested in the innermost invocation.
+pub(crate) fn nested_units() -> u64 {
+    EXECUTION_RECORD.with_borrow(|record| {
+        record
+            .frames
+            .last()
+            .map(|frame| frame.nested_units)
+            .unwrap_or_default()
+    })
+}
+
+pub(crate) fn record_syscall(name: &'static str, trace_position: usize, units: u64) {
+    EXECUTION_RECORD.with_borrow_mut(|record| {
+        let Some(frame) = record.frames.last() else {
+            return;
+        };
+        let invocation = frame.invocation;
+        record.syscalls.push(SyscallRecord {
+            invocation,
+            trace_position,
+            name,
+            units,
+        });
+    });
+}
+
+/// Recovers the VM from the pointer passed to builtin functions, the same way
+/// `declare_builtin_function` does.
+///
+/// # Safety
+///
+/// `vm` has to be the pointer a builtin function was invoked with, and no other
+/// reference to the VM may be alive while the returned one is used.
+pub(crate) unsafe fn vm_from_ptr<'a>(
+    vm: *mut EbpfVm<'a, InvokeContext<'static>>,
+) -> &'a mut EbpfVm<'a, InvokeContext<'static>> {
+    &mut *vm
+        .cast::<u64>()
+        .offset(-(get_runtime_environment_key() as isize))
+        .cast::<EbpfVm<'a, InvokeContext<'static>>>()
+}
@@ -0,0 +1,79 @@
+use solana_instruction::error::InstructionError;
+use solana_program_runtime::invoke_context::BuiltinFunctionWithContext;
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_program_runtime::loaded_programs::ProgramCacheEntry;
+use solana_program_runtime::loaded_programs::ProgramCacheEntryType;
+use solana_program_runtime::solana_sbpf::error::EbpfError;
+use solana_program_runtime::solana_sbpf::vm::EbpfVm;
+
+use super::enter_invocation;
+use super::exit_invocation;
+use super::vm_from_ptr;
+
+/// Name under which the original entrypoint of an instrumented native program is registered.
+const NATIVE_ENTRYPOINT_NAME: &str = "trident_entrypoint";
+
+/// Creates a cache entry for a native program whose invocations are recorded.
+pub(crate) fn instrumented_native_program(
+    name: &str,
+    entry: BuiltinFunctionWithContext,
+) -> ProgramCacheEntry {
+    let mut program = ProgramCacheEntry::new_builtin(0, name.len(), instrumented_native_entrypoint);
+    if let ProgramCacheEntryType::Builtin(builtin) = &mut program.program {
+        builtin
+            .register_function(NATIVE_ENTRYPOINT_NAME, entry)
+            .expect("Failed to register native entrypoint");
+    }
+    program
+}
+
+/// Looks up the original entrypoint of the invoked native program and records its invocation.
+fn instrumented_native_entrypoint(
+    vm: *mut EbpfVm<InvokeContext<'static>>,
+    arg0: u64,
+    arg1: u64,
+    arg2: u64,
+    arg3: u64,
+    arg4: u64,
+) {
+    let entry = {
+        let vm = unsafe { vm_from_ptr(vm) };
+        let invoke_context = &mut *vm.context_object_pointer;
+        match find_native_entrypoint(invoke_context) {
+            Ok((program_id, entry)) => {
+                enter_invocation(invoke_context, program_id);
+                entry
+            }
+            Err(err) => {
+                vm.program_result = Err(EbpfError::SyscallError(Box::new(err))).into();
+                return;
+            }
+        }
+    };
+
+    entry(vm, arg0, arg1, arg2, arg3, arg4);
+
+    let vm = unsafe { vm_from_ptr(vm) };
+    exit_invocation(vm.context_object_pointer, Vec::new(), None);
+}
+
+fn find_native_entrypoint(
+    invoke_context: &InvokeContext,
+) -> Result<(solana_pubkey::Pubkey, BuiltinFunctionWithContext), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let program_id = *instruction_context.get_last_program_key(transaction_context)?;
+
+    let program = invoke_context
+        .program_cache_for_tx_batch
+        .find(&program_id)
+        .ok_or(InstructionError::UnsupportedProgramId)?;
+    match &program.program {
+        ProgramCacheEntryType::Builtin(builtin) => builtin
+            .get_function_registry()
+            .lookup_by_name(NATIVE_ENTRYPOINT_NAME.as_bytes())
+            .map(|(_name, entry)| (program_id, entry))
+            .ok_or(InstructionError::UnsupportedProgramId),
+        _ => Err(InstructionError::UnsupportedProgramId),
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use solana_pubkey::Pubkey;
+
+use super::ExecutionRecord;
+use super::SyscallRecord;
+use crate::debug_info::SbfCallGraph;
+use crate::types::compute_unit_profile::ComputeUnitProfile;
+
+/// Builds the [`ComputeUnitProfile`] from the execution records of processed transactions.
+#[derive(Default)]
+pub(crate) struct ComputeUnitProfiler {
+    pub(crate) profile: ComputeUnitProfile,
+    /// Call graphs of traced programs, `None` if the ELF could not be parsed.
+    call_graphs: HashMap<Pubkey, Option<SbfCallGraph>>,
+}
+
+impl ComputeUnitProfiler {
+    /// Drops the cached call graph, has to be called whenever the program is redeployed.
+    pub(crate) fn invalidate(&mut self, program_id: &Pubkey) {
+        self.call_graphs.remove(program_id);
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        record: &ExecutionRecord,
+        get_elf: impl Fn(&Pubkey) -> Option<Vec<u8>>,
+    ) {
+        // Invocations are recorded before their children, so the stack of every
+        // child is known by the time it is walked.
+        let mut prefixes: Vec<Vec<String>> = vec![Vec::new(); record.invocations.len()];
+
+        for (index, invocation) in record.invocations.iter().enumerate() {
+            let program_id = invocation.program_id;
+            self.profile
+                .record_invocation(&program_id, invocation.units, invocation.self_units);
+            if let Some(parent) = invocation.parent {
+                self.profile.record_cpi(
+                    &record.invocations[parent].program_id,
+                    &program_id,
+                    invocation.units,
+                );
+            }
+
+            let mut syscalls: Vec<&SyscallRecord> = record
+                .syscalls
+                .iter()
+                .filter(|syscall| syscall.invocation == index)
+                .collect();
+            syscalls.sort_by_key(|syscall| syscall.trace_position);
+            for syscall in &syscalls {
+                self.profile.record_syscall(syscall.name, syscall.units);
+            }
+
+            let mut children: Vec<usize> = (index + 1..record.invocations.len())
+                .filter(|child| record.invocations[*child].parent == Some(index))
+                .collect();
+            children.sort_by_key(|child| record.invocations[*child].parent_trace_position);
+
+            let mut stack = std::mem::take(&mut prefixes[index]);
+            stack.push(program_id.to_string());
+
+            if invocation.pcs.is_empty() {
+                self.profile.record_stack(&stack, invocation.self_units);
+                for child in children {
+                    prefixes[child] = stack.clone();
+                }
+                continue;
+            }
+
+            self.call_graphs
+                .entry(program_id)
+                .or_insert_with(|| get_elf(&program_id).and_then(|elf| SbfCallGraph::parse(&elf)));
+            let Some(call_graph) = &self.call_graphs[&program_id] else {
+                self.profile.record_stack(&stack, invocation.self_units);
+                for child in children {
+                    prefixes[child] = stack.clone();
+                }
+                continue;
+            };
+
+            let root_depth = stack.len();
+            let mut stacks: HashMap<Vec<String>, u64> = HashMap::new();
+            let mut functions: HashMap<String, u64> = HashMap::new();
+            let mut pending_syscalls = syscalls.iter().peekable();
+            let mut pending_children = children.iter().peekable();
+
+            for (position, pc) in invocation.pcs.iter().enumerate() {
+                match position
+                    .checked_sub(1)
+                    .map(|previous| invocation.pcs[previous])
+                {
+                    None => stack.push(call_graph.function_name(*pc)),
+                    Some(previous) if *pc != previous + 1 => {
+                        if call_graph.is_call(previous) {
+                            stack.push(call_graph.function_name(*pc));
+                        } else if call_graph.is_return(previous) && stack.len() > root_depth + 1 {
+                            stack.pop();
+                        }
+                    }
+                    Some(_) => {}
+                }
+
+                // every SBF instruction consumes a single compute unit
+                add_units(&mut stacks, &stack, 1);
+                let function = stack.last().expect("Stack contains the program frame");
+                match functions.get_mut(function) {
+                    Some(units) => *units += 1,
+                    None => {
+                        functions.insert(function.clone(), 1);
+                    }
+                }
+
+                let executed = position + 1;
+                while let Some(child) = pending_children
+                    .next_if(|child| record.invocations[**child].parent_trace_position <= executed)
+                {
+                    let mut prefix = stack.clone();
+                    prefix.extend(
+                        syscalls
+                            .iter()
+                            .find(|syscall| {
+                                syscall.trace_position
+                                    == record.invocations[*child].parent_trace_position
+                            })
+                            .map(|syscall| syscall.name.to_string()),
+                    );
+                    prefixes[*child] = prefix;
+                }
+                while let Some(syscall) =
+                    pending_syscalls.next_if(|syscall| syscall.trace_position <= executed)
+                {
+                    stack.push(syscall.name.to_string());
+                    add_units(&mut stacks, &stack, syscall.units);
+                    stack.pop();
+                }
+            }
+
+            for child in pending_children {
+                prefixes[*child] = stack.clone();
+            }
+
+            // Units which are neither instructions nor syscalls, e.g. charged by the loader
+            let syscall_units: u64 = syscalls.iter().map(|syscall| syscall.units).sum();
+            let overhead = invocation
+                .self_units
+                .saturating_sub(invocation.pcs.len() as u64)
+                .saturating_sub(syscall_units);
+            stack.truncate(root_depth);
+            add_units(&mut stacks, &stack, overhead);
+
+            for (frames, units) in stacks {
+                self.profile.record_stack(&frames, units);
+            }
+            for (function, units) in functions {
+                self.profile.record_function(&program_id, &function, units);
+            }
+        }
+    }
+}
+
+fn add_units(stacks: &mut HashMap<Vec<String>, u64>, stack: &[String], units: u64) {
+    match stacks.get_mut(stack) {
+        Some(stack_units) => *stack_units = stack_units.saturating_add(units),
+        None => {
+            stacks.insert(stack.to_vec(), units);
+        }
+    }
+}
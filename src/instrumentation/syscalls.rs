@@ -0,0 +1,142 @@
+use std::sync::RwLock;
+
+use solana_program_runtime::invoke_context::BuiltinFunctionWithContext;
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_program_runtime::solana_sbpf::error::EbpfError;
+use solana_program_runtime::solana_sbpf::program::BuiltinProgram;
+use solana_program_runtime::solana_sbpf::vm::ContextObject;
+use solana_program_runtime::solana_sbpf::vm::EbpfVm;
+
+use super::nested_units;
+use super::record_syscall;
+use super::vm_from_ptr;
+use crate::fault_injection::syscall_fault;
+
+/// Wrapped syscalls, the position in the table selects the wrapper.
+static SYSCALLS: RwLock<Vec<(&'static str, BuiltinFunctionWithContext)>> = RwLock::new(Vec::new());
+
+macro_rules! syscall_wrappers {
+    ($wrapper:ident) => {
+        syscall_wrappers!(@ $wrapper
+            0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+            32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61 62 63
+            64 65 66 67 68 69 70 71 72 73 74 75 76 77 78 79 80 81 82 83 84 85 86 87 88 89 90 91 92 93 94 95
+            96 97 98 99 100 101 102 103 104 105 106 107 108 109 110 111 112 113 114 115 116 117 118 119 120
+            121 122 123 124 125 126 127
+        )
+    };
+    (@ $wrapper:ident $($index:literal)*) => {
+        [$($wrapper::<$index> as BuiltinFunctionWithContext),*]
+    };
+}
+
+/// Syscall wrappers, one for every slot of [`SYSCALLS`].
+const INSTRUMENTED_SYSCALLS: [BuiltinFunctionWithContext; 128] =
+    syscall_wrappers!(instrumented_syscall);
+
+/// Syscall wrappers which inject faults, one for every slot of [`SYSCALLS`].
+const FAULTY_SYSCALLS: [BuiltinFunctionWithContext; 128] = syscall_wrappers!(faulty_syscall);
+
+/// Returns a copy of the loader whose syscalls record the compute units they consume.
+pub(crate) fn instrument_syscalls(
+    loader: &BuiltinProgram<InvokeContext<'static>>,
+) -> BuiltinProgram<InvokeContext<'static>> {
+    wrap_syscalls(loader, &INSTRUMENTED_SYSCALLS)
+}
+
+/// Returns a copy of the loader whose syscalls fail as configured by the active faults.
+pub(crate) fn inject_syscall_faults(
+    loader: &BuiltinProgram<InvokeContext<'static>>,
+) -> BuiltinProgram<InvokeContext<'static>> {
+    wrap_syscalls(loader, &FAULTY_SYSCALLS)
+}
+
+fn wrap_syscalls(
+    loader: &BuiltinProgram<InvokeContext<'static>>,
+    wrappers: &[BuiltinFunctionWithContext; 128],
+) -> BuiltinProgram<InvokeContext<'static>> {
+    let mut wrapped = BuiltinProgram::new_loader(loader.get_config().clone());
+    for (_key, (name, function)) in loader.get_function_registry().iter() {
+        let name = String::from_utf8_lossy(name);
+        let function = register_syscall(&name, function)
+            .map(|index| wrappers[index])
+            .unwrap_or(function);
+        wrapped
+            .register_function(&name, function)
+            .expect("Failed to register syscall");
+    }
+    wrapped
+}
+
+fn register_syscall(name: &str, function: BuiltinFunctionWithContext) -> Option<usize> {
+    let mut syscalls = SYSCALLS.write().expect("Failed to write syscall table");
+    if let Some(index) = syscalls.iter().position(|(registered_name, registered)| {
+        *registered_name == name && std::ptr::fn_addr_eq(*registered, function)
+    }) {
+        return Some(index);
+    }
+    if syscalls.len() == INSTRUMENTED_SYSCALLS.len() {
+        log::warn!("Syscall {name} is not wrapped, the syscall table is full");
+        return None;
+    }
+    // The table only ever holds the syscalls of the runtime environments, leaking the names is fine.
+    syscalls.push((name.to_string().leak(), function));
+    Some(syscalls.len() - 1)
+}
+
+fn instrumented_syscall<const INDEX: usize>(
+    vm: *mut EbpfVm<InvokeContext<'static>>,
+    arg0: u64,
+    arg1: u64,
+    arg2: u64,
+    arg3: u64,
+    arg4: u64,
+) {
+    let (name, function) = SYSCALLS.read().expect("Failed to read syscall table")[INDEX];
+
+    let (remaining_units, trace_position) = {
+        let vm = unsafe { vm_from_ptr(vm) };
+        // Instructions executed since the last syscall are consumed by the syscall adapter
+        let pending_units = vm
+            .previous_instruction_meter
+            .saturating_sub(vm.due_insn_count);
+        let invoke_context = &vm.context_object_pointer;
+        let trace_position = invoke_context
+            .syscall_context
+            .last()
+            .and_then(|syscall_context| syscall_context.as_ref())
+            .map(|syscall_context| syscall_context.trace_log.len())
+            .unwrap_or_default();
+        (
+            invoke_context.get_remaining().saturating_sub(pending_units),
+            trace_position,
+        )
+    };
+    let nested_units_before = nested_units();
+
+    function(vm, arg0, arg1, arg2, arg3, arg4);
+
+    let vm = unsafe { vm_from_ptr(vm) };
+    let units = remaining_units.saturating_sub(vm.context_object_pointer.get_remaining());
+    let nested = nested_units().saturating_sub(nested_units_before);
+    record_syscall(name, trace_position, units.saturating_sub(nested));
+}
+
+fn faulty_syscall<const INDEX: usize>(
+    vm: *mut EbpfVm<InvokeContext<'static>>,
+    arg0: u64,
+    arg1: u64,
+    arg2: u64,
+    arg3: u64,
+    arg4: u64,
+) {
+    let (name, function) = SYSCALLS.read().expect("Failed to read syscall table")[INDEX];
+
+    match syscall_fault(name) {
+        Some(err) => {
+            let vm = unsafe { vm_from_ptr(vm) };
+            vm.program_result = Err(EbpfError::SyscallError(Box::new(err))).into();
+        }
+        None => function(vm, arg0, arg1, arg2, arg3, arg4),
+    }
+}
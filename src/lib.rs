@@ -1,16 +1,28 @@
 mod accounts_database;
 mod builder;
+mod debug_info;
+mod fault_injection;
+mod instrumentation;
 mod methods;
+mod mocking;
+mod program_cache;
+#[cfg(feature = "syscall-v2")]
+mod syscall_stubs;
+#[cfg(test)]
+mod test_utils;
 mod trident_fork_graphs;
 pub mod trident_svm_log;
 mod utils;
 
 #[cfg(feature = "syscall-v2")]
 pub mod builtin_function;
+pub mod differential_runner;
 pub mod trident_svm;
 pub mod types;
 
 pub mod processor {
+    #[cfg(feature = "syscall-v2")]
+    pub use crate::builtin_function::panic_error;
     #[cfg(feature = "syscall-v2")]
     pub use crate::builtin_function::post_invocation;
     #[cfg(feature = "syscall-v2")]
@@ -18,6 +30,10 @@ pub mod processor {
 
     #[cfg(feature = "syscall-v2")]
     pub use solana_program_entrypoint::deserialize;
+    #[cfg(feature = "syscall-v2")]
+    pub use solana_program_error::ProgramError;
+    #[cfg(feature = "syscall-v2")]
+    pub use solana_program_error::ProgramResult;
 
     pub use solana_account_info as account_info;
     pub use solana_instruction::error::InstructionError;
@@ -30,6 +46,34 @@ pub mod processor {
 
 pub mod prelude {
     pub use super::trident_svm_log;
+    pub use crate::differential_runner::DifferentialRunner;
+    pub use crate::types::bundled_program::BundledProgram;
+    pub use crate::types::chainlink_feed::ChainlinkFeed;
+    pub use crate::types::chainlink_feed::ChainlinkRound;
+    pub use crate::types::compute_unit_profile::ComputeUnitProfile;
+    pub use crate::types::coverage_report::CoverageReport;
+    pub use crate::types::execution_difference::DifferentialReport;
+    pub use crate::types::execution_difference::ExecutionDifference;
+    pub use crate::types::execution_mode::ExecutionMode;
+    pub use crate::types::fault_injection::FaultInjection;
+    pub use crate::types::fault_injection::SyscallFault;
+    pub use crate::types::name_record::NameRecord;
+    pub use crate::types::nft_metadata::MintedNft;
+    pub use crate::types::nft_metadata::NftCollection;
+    pub use crate::types::nft_metadata::NftCreator;
+    pub use crate::types::nft_metadata::NftMetadata;
+    pub use crate::types::nft_metadata::TokenStandard;
+    pub use crate::types::program_mock::MockAccount;
+    pub use crate::types::program_mock::MockInvocation;
+    pub use crate::types::pyth_price_update::PythPriceUpdate;
+    pub use crate::types::sbf_coverage::SbfCoverage;
+    pub use crate::types::token_state::AccountExtension;
+    pub use crate::types::token_state::MintExtension;
+    pub use crate::types::token_state::TokenAccount;
+    pub use crate::types::token_state::TokenMint;
+    pub use crate::types::transaction_recording::RecordedTransaction;
+    pub use crate::types::transaction_recording::ReplayMismatch;
+    pub use crate::types::transaction_recording::TransactionRecording;
     pub use crate::types::transaction_result::TridentTransactionProcessingResult;
     pub use log::Level;
     pub use solana_svm;
@@ -1,3 +1,14 @@
 mod trident_svm_accounts;
+mod trident_svm_address_lookup_tables;
+mod trident_svm_coverage;
+mod trident_svm_fault_injection;
+mod trident_svm_loader_deployment;
+mod trident_svm_mocks;
+mod trident_svm_name_service;
+mod trident_svm_nfts;
+mod trident_svm_oracles;
+mod trident_svm_profiling;
 mod trident_svm_programs;
+mod trident_svm_recording;
+mod trident_svm_tokens;
 mod trident_svm_transactions;
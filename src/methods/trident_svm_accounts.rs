@@ -3,11 +3,15 @@ use solana_sysvar_id::SysvarId;
 
 use solana_account::AccountSharedData;
 use solana_account::ReadableAccount;
+use solana_account::WritableAccount;
 
 use solana_keypair::Keypair;
 use solana_pubkey::Pubkey;
+use solana_rent::Rent;
+use solana_signer::Signer;
 
 use crate::trident_svm::TridentSVM;
+use crate::trident_svm::PAYER_LAMPORTS;
 
 impl TridentSVM {
     pub fn get_account(&self, pubkey: &Pubkey) -> Option<AccountSharedData> {
@@ -22,6 +26,23 @@ impl TridentSVM {
         }
     }
 
+    /// Stores a rent exempt account holding the data.
+    pub(crate) fn set_rent_exempt_account(
+        &mut self,
+        address: &Pubkey,
+        data: &[u8],
+        owner: &Pubkey,
+        permanent: bool,
+    ) {
+        let mut account = AccountSharedData::new(
+            self.get_sysvar::<Rent>().minimum_balance(data.len()),
+            data.len(),
+            owner,
+        );
+        account.set_data_from_slice(data);
+        self.set_account(address, &account, permanent);
+    }
+
     pub fn get_sysvar<T: Sysvar + SysvarId>(&self) -> T {
         self.accounts.get_sysvar()
     }
@@ -32,6 +53,58 @@ impl TridentSVM {
     pub fn get_payer(&self) -> Keypair {
         self.payer.insecure_clone()
     }
+
+    /// Replaces the payer of the transactions, the new payer is funded with the balance
+    /// of the default payer if it holds no lamports.
+    pub fn set_payer(&mut self, payer: Keypair) {
+        let pubkey = payer.pubkey();
+        if self
+            .get_account(&pubkey)
+            .is_none_or(|account| account.lamports() == 0)
+        {
+            let account =
+                AccountSharedData::new(PAYER_LAMPORTS, 0, &solana_sdk_ids::system_program::id());
+            self.accounts.set_permanent_account(&pubkey, &account);
+        }
+        self.payer = payer;
+    }
+
+    /// Adds lamports to the account, a system account is created if it does not exist.
+    ///
+    /// Permanent accounts stay permanent, other accounts are stored as temporary.
+    pub fn airdrop(&mut self, pubkey: &Pubkey, lamports: u64) {
+        let mut account = self
+            .get_account(pubkey)
+            .unwrap_or_else(|| AccountSharedData::new(0, 0, &solana_sdk_ids::system_program::id()));
+        account.set_lamports(account.lamports().saturating_add(lamports));
+        let permanent = self.accounts.get_permanent_account(pubkey).is_some();
+        self.set_account(pubkey, &account, permanent);
+    }
+
+    /// Creates a keypair holding the lamports in a temporary system account.
+    ///
+    /// Keypairs are derived from the seed of the builder if set, so the n-th keypair is
+    /// the same in every run.
+    pub fn create_funded_keypair(&mut self, lamports: u64) -> Keypair {
+        let keypair = self.next_keypair();
+        self.airdrop(&keypair.pubkey(), lamports);
+        keypair
+    }
+    /// Creates a new keypair, derived from the seed of the builder if set.
+    pub(crate) fn next_keypair(&mut self) -> Keypair {
+        match self.keypair_seed {
+            Some(seed) => {
+                let secret_key = solana_sha256_hasher::hashv(&[
+                    &seed.to_le_bytes(),
+                    &self.derived_keypairs.to_le_bytes(),
+                ]);
+                self.derived_keypairs += 1;
+                Keypair::new_from_array(secret_key.to_bytes())
+            }
+            None => Keypair::new(),
+        }
+    }
+
     pub(crate) fn settle_accounts(&mut self, accounts: &[(Pubkey, AccountSharedData)]) {
         for account in accounts {
             if !account.1.executable() && account.1.owner() != &solana_sdk_ids::sysvar::id() {
@@ -46,3 +119,101 @@ impl TridentSVM {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use solana_account::ReadableAccount;
+    use solana_clock::Clock;
+    use solana_hash::Hash;
+    use solana_instruction::AccountMeta;
+    use solana_instruction::Instruction;
+    use solana_keypair::Keypair;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+    use solana_slot_hashes::SlotHashes;
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+    use solana_transaction::Transaction;
+
+    use crate::trident_svm::TridentSVM;
+
+    #[test]
+    fn test_funded_keypairs() {
+        let payer = Keypair::new();
+        let mut builder = TridentSVM::builder();
+        builder.without_bundled_programs();
+        builder.with_payer(payer.insecure_clone());
+        builder.with_seed(42);
+        let mut svm = builder.build();
+        assert_eq!(svm.get_payer().pubkey(), payer.pubkey());
+
+        let sender = svm.create_funded_keypair(1_000_000_000);
+        let recipient = svm.create_funded_keypair(0);
+        assert_ne!(sender.pubkey(), recipient.pubkey());
+
+        // keypairs derived from the same seed are the same in every instance
+        let mut other = TridentSVM::builder();
+        other.without_bundled_programs();
+        other.with_seed(42);
+        let mut other = other.build();
+        assert_eq!(other.create_funded_keypair(0).pubkey(), sender.pubkey());
+
+        // SystemInstruction::Transfer signed by the funded keypair
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&400_000_000u64.to_le_bytes());
+        let instruction = Instruction::new_with_bytes(
+            solana_sdk_ids::system_program::id(),
+            &data,
+            vec![
+                AccountMeta::new(sender.pubkey(), true),
+                AccountMeta::new(recipient.pubkey(), false),
+            ],
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer, &sender],
+            Hash::default(),
+        );
+        let result = svm.process_transaction_with_settle(transaction);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+
+        svm.airdrop(&recipient.pubkey(), 100);
+        assert_eq!(
+            svm.get_account(&recipient.pubkey()).unwrap().lamports(),
+            400_000_100
+        );
+        let unknown = Pubkey::new_unique();
+        svm.airdrop(&unknown, 5);
+        assert_eq!(svm.get_account(&unknown).unwrap().lamports(), 5);
+    }
+
+    #[test]
+    fn test_seeded_instances() {
+        let build = |seed: u64| {
+            let mut builder = TridentSVM::builder();
+            builder.without_bundled_programs();
+            builder.with_seed(seed);
+            builder.build()
+        };
+        let mut svm = build(7);
+        let mut other = build(7);
+
+        assert_eq!(svm.get_payer().pubkey(), other.get_payer().pubkey());
+        assert_ne!(svm.get_payer().pubkey(), build(8).get_payer().pubkey());
+        assert_eq!(
+            svm.get_sysvar::<Clock>().unix_timestamp,
+            other.get_sysvar::<Clock>().unix_timestamp
+        );
+        assert_eq!(
+            svm.get_sysvar::<SlotHashes>(),
+            other.get_sysvar::<SlotHashes>()
+        );
+        assert_ne!(svm.get_sysvar::<SlotHashes>()[0].1, Hash::default());
+        assert_eq!(
+            svm.create_funded_keypair(1).pubkey(),
+            other.create_funded_keypair(1).pubkey()
+        );
+    }
+}
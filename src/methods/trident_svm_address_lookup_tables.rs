@@ -0,0 +1,101 @@
+use std::borrow::Cow;
+
+use solana_account::ReadableAccount;
+use solana_address_lookup_table_interface::state::AddressLookupTable;
+use solana_address_lookup_table_interface::state::LookupTableMeta;
+use solana_clock::Clock;
+use solana_pubkey::Pubkey;
+
+use crate::trident_svm::TridentSVM;
+
+impl TridentSVM {
+    /// Creates an active lookup table at the address derived from the authority and the
+    /// current slot, all addresses are usable in the current slot.
+    pub fn create_address_lookup_table(
+        &mut self,
+        authority: &Pubkey,
+        addresses: &[Pubkey],
+    ) -> Pubkey {
+        let slot = self.get_sysvar::<Clock>().slot;
+        let (address, _) = Pubkey::find_program_address(
+            &[authority.as_ref(), &slot.to_le_bytes()],
+            &solana_sdk_ids::address_lookup_table::id(),
+        );
+
+        let lookup_table = AddressLookupTable {
+            meta: LookupTableMeta {
+                last_extended_slot: slot,
+                last_extended_slot_start_index: addresses.len() as u8,
+                ..LookupTableMeta::new(*authority)
+            },
+            addresses: Cow::Borrowed(addresses),
+        };
+        let data = lookup_table
+            .serialize_for_tests()
+            .expect("Lookup table has to be serializable");
+
+        self.set_rent_exempt_account(
+            &address,
+            &data,
+            &solana_sdk_ids::address_lookup_table::id(),
+            false,
+        );
+        address
+    }
+
+    /// Addresses stored in the lookup table, `None` if the account is not a lookup table.
+    pub fn get_address_lookup_table(&self, address: &Pubkey) -> Option<Vec<Pubkey>> {
+        let account = self.get_account(address)?;
+        if account.owner() != &solana_sdk_ids::address_lookup_table::id() {
+            return None;
+        }
+        AddressLookupTable::deserialize(account.data())
+            .ok()
+            .map(|lookup_table| lookup_table.addresses.to_vec())
+    }
+}
+
+#[cfg(all(test, feature = "address-lookup-table"))]
+mod tests {
+    use solana_address_lookup_table_interface::instruction::extend_lookup_table;
+    use solana_hash::Hash;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+    use solana_transaction::Transaction;
+
+    use crate::trident_svm::TridentSVM;
+
+    #[test]
+    fn test_extend_address_lookup_table() {
+        let mut svm = TridentSVM::default();
+        let payer = svm.get_payer();
+        let addresses = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let lookup_table = svm.create_address_lookup_table(&payer.pubkey(), &addresses);
+        assert_eq!(
+            svm.get_address_lookup_table(&lookup_table).unwrap(),
+            addresses
+        );
+
+        let new_address = Pubkey::new_unique();
+        let transaction = Transaction::new_signed_with_payer(
+            &[extend_lookup_table(
+                lookup_table,
+                payer.pubkey(),
+                Some(payer.pubkey()),
+                vec![new_address],
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        let result = svm.process_transaction_with_settle(transaction);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+        assert_eq!(
+            svm.get_address_lookup_table(&lookup_table).unwrap(),
+            [addresses[0], addresses[1], new_address]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use solana_program_runtime::loaded_programs::ProgramCacheEntryType;
+use solana_pubkey::Pubkey;
+
+use crate::debug_info::disassemble;
+use crate::debug_info::SbfDebugInfo;
+use crate::instrumentation::ExecutionRecord;
+use crate::trident_svm::TridentSVM;
+use crate::types::coverage_report::CoverageGranularity;
+use crate::types::coverage_report::CoverageReport;
+use crate::types::coverage_report::FileCoverage;
+use crate::types::sbf_coverage::ProgramCoverage;
+use crate::types::sbf_coverage::SbfCoverage;
+use crate::types::sbf_coverage::SourceLocation;
+
+impl TridentSVM {
+    pub(crate) fn enable_sbf_coverage(&mut self) {
+        self.sbf_coverage = Some(SbfCoverage::default());
+        self.instrument_program_execution();
+    }
+
+    /// Coverage accumulated from SBF programs, `None` if coverage collection is not enabled.
+    pub fn get_sbf_coverage(&self) -> Option<&SbfCoverage> {
+        self.sbf_coverage.as_ref()
+    }
+
+    /// Returns the accumulated SBF coverage and starts collecting from scratch.
+    pub fn take_sbf_coverage(&mut self) -> Option<SbfCoverage> {
+        self.sbf_coverage.as_mut().map(std::mem::take)
+    }
+
+    /// Maps the coverage of the program to source lines, using the DWARF information of the
+    /// deployed ELF. Returns `None` if there is no coverage of the program or the ELF does not
+    /// contain debug information.
+    pub fn get_sbf_line_coverage(
+        &self,
+        program_id: &Pubkey,
+    ) -> Option<BTreeMap<SourceLocation, u64>> {
+        let coverage = self.sbf_coverage.as_ref()?.program(program_id)?;
+        let elf = self.get_program_elf(program_id)?;
+        let debug_info = SbfDebugInfo::parse(&elf)?;
+
+        Some(line_hits(&debug_info, coverage))
+    }
+
+    /// Builds a line coverage report of all covered programs, which can be exported as lcov
+    /// or HTML. Programs without debug information are reported per disassembled instruction.
+    pub fn get_sbf_coverage_report(&self) -> Option<CoverageReport> {
+        let sbf_coverage = self.sbf_coverage.as_ref()?;
+        let environment = self
+            .processor
+            .program_cache
+            .read()
+            .expect("Failed to read program cache")
+            .environments
+            .program_runtime_v1
+            .clone();
+
+        let mut report = CoverageReport::default();
+        let mut programs: Vec<_> = sbf_coverage.programs().collect();
+        programs.sort_by_key(|(program_id, _)| **program_id);
+
+        for (program_id, coverage) in programs {
+            let Some(elf) = self.get_program_elf(program_id) else {
+                continue;
+            };
+
+            if let Some(debug_info) = SbfDebugInfo::parse(&elf) {
+                let mut files: BTreeMap<String, BTreeMap<u32, u64>> = BTreeMap::new();
+                for location in debug_info.instrumented_lines() {
+                    files
+                        .entry(location.file)
+                        .or_default()
+                        .insert(location.line, 0);
+                }
+                for (location, hits) in line_hits(&debug_info, coverage) {
+                    files
+                        .entry(location.file)
+                        .or_default()
+                        .insert(location.line, hits);
+                }
+                for (path, lines) in files {
+                    let listing = std::fs::read_to_string(&path)
+                        .ok()
+                        .map(|source| source.lines().map(str::to_string).collect());
+                    report.add_file(FileCoverage {
+                        program_id: *program_id,
+                        path,
+                        granularity: CoverageGranularity::SourceLines,
+                        lines,
+                        listing,
+                    });
+                }
+            } else if let Some(instructions) = disassemble(&elf, environment.clone()) {
+                let mut lines = BTreeMap::new();
+                let mut listing = Vec::with_capacity(instructions.len());
+                for (index, (pc, assembly)) in instructions.into_iter().enumerate() {
+                    let hits = coverage.pc_hits().get(&pc).copied().unwrap_or_default();
+                    lines.insert(index as u32 + 1, hits);
+                    listing.push(format!("{pc:>6}  {assembly}"));
+                }
+                report.add_file(FileCoverage {
+                    program_id: *program_id,
+                    path: format!("{program_id}.s"),
+                    granularity: CoverageGranularity::Disassembly,
+                    lines,
+                    listing: Some(listing),
+                });
+            }
+        }
+        Some(report)
+    }
+
+    pub(crate) fn record_sbf_coverage(&mut self, record: &ExecutionRecord) {
+        if let Some(coverage) = self.sbf_coverage.as_mut() {
+            for invocation in &record.invocations {
+                if invocation.pcs.is_empty() {
+                    continue;
+                }
+                let text = match invocation.program.as_deref().map(|entry| &entry.program) {
+                    Some(ProgramCacheEntryType::Loaded(executable)) => {
+                        executable.get_text_bytes().1
+                    }
+                    _ => &[],
+                };
+                coverage.record(&invocation.program_id, &invocation.pcs, text);
+            }
+        }
+    }
+}
+
+/// Execution count of every covered source line, which is the highest
+/// execution count among the instructions of the line.
+fn line_hits(
+    debug_info: &SbfDebugInfo,
+    coverage: &ProgramCoverage,
+) -> BTreeMap<SourceLocation, u64> {
+    let mut lines = BTreeMap::new();
+    for (pc, hits) in coverage.pc_hits() {
+        if let Some(location) = debug_info.locate(*pc) {
+            let line_hits = lines.entry(location).or_default();
+            *line_hits = std::cmp::max(*line_hits, *hits);
+        }
+    }
+    lines
+}
+
+#[cfg(all(test, feature = "spl-token"))]
+mod tests {
+    use solana_pubkey::pubkey;
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+
+    use crate::test_utils::initialize_mint_transaction;
+    use crate::trident_svm::TridentSVM;
+    use crate::types::coverage_report::CoverageGranularity;
+
+    #[test]
+    fn test_sbf_coverage_of_spl_token() {
+        let token_program = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+        let mut svm = TridentSVM::builder().with_sbf_coverage().build();
+        let (_, transaction) = initialize_mint_transaction(&mut svm, &token_program);
+
+        let result = svm.process_transaction_with_settle(transaction);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+
+        // spl token is deployed without debug information, the report falls back to disassembly
+        let report = svm.get_sbf_coverage_report().unwrap();
+        let token_report = &report.files()[0];
+        assert_eq!(report.files().len(), 1);
+        assert_eq!(token_report.granularity, CoverageGranularity::Disassembly);
+        assert!(token_report.lines_hit() > 10);
+        assert!(token_report.lines_hit() < token_report.lines_found());
+
+        let mut lcov = Vec::new();
+        report.write_lcov(&mut lcov).unwrap();
+        let lcov = String::from_utf8(lcov).unwrap();
+        assert!(lcov.starts_with(&format!("TN:{token_program}\nSF:{token_program}.s\n")));
+        assert!(lcov.ends_with("end_of_record\n"));
+
+        let coverage = svm.take_sbf_coverage().unwrap();
+        let token_coverage = coverage.program(&token_program).unwrap();
+        assert!(token_coverage.pc_hits().len() > 10);
+        assert!(token_coverage.basic_blocks().count() > 1);
+        assert!(svm.get_sbf_coverage().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::trident_svm::TridentSVM;
+use crate::types::fault_injection::FaultInjection;
+
+impl TridentSVM {
+    /// Injects the faults into all transactions processed from now on, replacing
+    /// the previous ones. Syscall faults are counted per transaction.
+    ///
+    /// Syscall faults change the runtime environment of the program cache, an instance
+    /// sharing the cache with workers gets its own cache, so the faults never reach them.
+    pub fn set_fault_injection(&mut self, faults: FaultInjection) {
+        let environment_key = self.environment_key();
+        self.fault_injection = faults;
+        // syscall faults require a runtime environment with wrapped syscalls
+        if self.environment_key() != environment_key {
+            self.reload_programs();
+        }
+    }
+
+    /// Stops injecting faults.
+    pub fn clear_fault_injection(&mut self) {
+        self.set_fault_injection(FaultInjection::default());
+    }
+
+    pub fn get_fault_injection(&self) -> &FaultInjection {
+        &self.fault_injection
+    }
+}
+
+#[cfg(all(test, feature = "spl-token", feature = "spl-associated-token-account"))]
+mod tests {
+    use solana_hash::Hash;
+    use solana_instruction::error::InstructionError;
+    use solana_instruction::AccountMeta;
+    use solana_instruction::Instruction;
+    use solana_keypair::Keypair;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+    use solana_transaction::Transaction;
+    use solana_transaction_error::TransactionError;
+
+    use crate::trident_svm::TridentSVM;
+    use crate::types::bundled_program::BundledProgram;
+    use crate::types::fault_injection::FaultInjection;
+    use crate::types::token_state::TokenAccount;
+
+    /// Creates an associated token account, which invokes the system and token programs.
+    fn create_associated_token_account(
+        svm: &mut TridentSVM,
+        mint: &Pubkey,
+    ) -> Result<(), TransactionError> {
+        let token_program = BundledProgram::SplToken.program_id();
+        let payer = svm.get_payer();
+        let wallet = Keypair::new().pubkey();
+        let instruction = Instruction::new_with_bytes(
+            BundledProgram::AssociatedToken.program_id(),
+            &[0],
+            vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(
+                    TokenAccount::associated_address(&wallet, mint, &token_program),
+                    false,
+                ),
+                AccountMeta::new_readonly(wallet, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(solana_sdk_ids::system_program::id(), false),
+                AccountMeta::new_readonly(token_program, false),
+            ],
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        let result = svm.process_transaction_with_settle(transaction);
+        result.get_result().processing_results[0].flattened_result()
+    }
+
+    #[test]
+    fn test_fault_injection() {
+        let mut svm = TridentSVM::default();
+        let mint = Keypair::new().pubkey();
+        svm.create_mint(
+            &mint,
+            &Pubkey::new_unique(),
+            6,
+            &BundledProgram::SplToken.program_id(),
+        );
+        assert_eq!(create_associated_token_account(&mut svm, &mint), Ok(()));
+
+        svm.set_fault_injection(
+            FaultInjection::new().with_missing_account(svm.get_payer().pubkey()),
+        );
+        assert_eq!(
+            create_associated_token_account(&mut svm, &mint),
+            Err(TransactionError::AccountNotFound)
+        );
+
+        svm.set_fault_injection(FaultInjection::new().with_max_instruction_stack_depth(1));
+        assert_eq!(
+            create_associated_token_account(&mut svm, &mint),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::CallDepth
+            ))
+        );
+
+        svm.set_fault_injection(FaultInjection::new().with_compute_unit_limit(1_000));
+        assert_eq!(
+            create_associated_token_account(&mut svm, &mint),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::ProgramFailedToComplete
+            ))
+        );
+
+        // the first CPI, into the token program, succeeds, the second one fails
+        svm.set_fault_injection(FaultInjection::new().with_syscall_fault(
+            "sol_invoke_signed_rust",
+            1,
+            InstructionError::Custom(42),
+        ));
+        assert_eq!(
+            create_associated_token_account(&mut svm, &mint),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(42)
+            ))
+        );
+        // calls are counted per transaction
+        assert_eq!(
+            create_associated_token_account(&mut svm, &mint),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(42)
+            ))
+        );
+
+        svm.clear_fault_injection();
+        assert_eq!(create_associated_token_account(&mut svm, &mint), Ok(()));
+    }
+
+    #[test]
+    fn test_syscall_faults_are_not_shared_with_workers() {
+        let mut svm = TridentSVM::default();
+        let mint = Keypair::new().pubkey();
+        svm.create_mint(
+            &mint,
+            &Pubkey::new_unique(),
+            6,
+            &BundledProgram::SplToken.program_id(),
+        );
+        let mut worker = svm.new_worker();
+
+        svm.set_fault_injection(FaultInjection::new().with_syscall_fault(
+            "sol_invoke_signed_rust",
+            0,
+            InstructionError::Custom(42),
+        ));
+        assert_eq!(
+            create_associated_token_account(&mut svm, &mint),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(42)
+            ))
+        );
+        assert_eq!(create_associated_token_account(&mut worker, &mint), Ok(()));
+    }
+}
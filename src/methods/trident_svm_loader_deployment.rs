@@ -0,0 +1,288 @@
+use solana_account::AccountSharedData;
+use solana_account::ReadableAccount;
+use solana_account::WritableAccount;
+use solana_clock::Clock;
+use solana_hash::Hash;
+use solana_instruction::error::InstructionError;
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_loader_v3_interface::instruction as loader_instruction;
+use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_pubkey::Pubkey;
+use solana_rent::Rent;
+use solana_signer::Signer;
+use solana_svm::transaction_processing_result::ProcessedTransaction;
+use solana_transaction::Transaction;
+use solana_transaction_error::TransactionError;
+
+use crate::trident_svm::derive_from_seed;
+use crate::trident_svm::TridentSVM;
+use crate::types::trident_program::ProgramLoader;
+use crate::types::trident_program::TridentProgram;
+
+/// Maximum size of a serialized transaction, see `solana_packet::PACKET_DATA_SIZE`.
+const PACKET_DATA_SIZE: usize = 1280 - 40 - 8;
+
+impl TridentSVM {
+    /// Deploys the program through the BPF Loader Upgradeable, processing the same
+    /// `InitializeBuffer`, `Write` and `DeployWithMaxDataLen` transactions as `solana program deploy`.
+    ///
+    /// Unlike [`TridentSVM::deploy_binary_program`], the ELF has to pass the verifier of the
+    /// configured feature set, the payer funds the buffer and ProgramData accounts, and the
+    /// ProgramData records the current slot. The payer is used as the upgrade authority during
+    /// deployment, afterwards the authority is set to the authority of the program.
+    ///
+    /// The program account is created directly, as its keypair is not available. Programs of
+    /// other loaders cannot be deployed this way, the runtime no longer supports the management
+    /// instructions of `bpf_loader` and `bpf_loader_deprecated`.
+    pub fn deploy_program_with_loader(
+        &mut self,
+        program: &TridentProgram,
+    ) -> Result<(), TransactionError> {
+        if program.loader != ProgramLoader::Upgradeable {
+            return Err(TransactionError::InstructionError(
+                0,
+                InstructionError::UnsupportedProgramId,
+            ));
+        }
+
+        let payer = self.payer.insecure_clone();
+        // not taken from `next_keypair`, so deployments do not shift the funded keypairs
+        let buffer = match self.keypair_seed {
+            Some(seed) => {
+                let domain = [b"loader-buffer".as_slice(), program.program_id.as_ref()].concat();
+                Keypair::new_from_array(derive_from_seed(seed, &domain).to_bytes())
+            }
+            None => Keypair::new(),
+        };
+        let program_len = program.data.len();
+        let rent = Rent::default();
+
+        if self.get_account(&program.program_id).is_some() {
+            return Err(TransactionError::InstructionError(
+                0,
+                InstructionError::AccountAlreadyInitialized,
+            ));
+        }
+
+        let create_buffer = loader_instruction::create_buffer(
+            &payer.pubkey(),
+            &buffer.pubkey(),
+            &payer.pubkey(),
+            rent.minimum_balance(UpgradeableLoaderState::size_of_buffer(program_len)),
+            program_len,
+        )
+        .map_err(|err| TransactionError::InstructionError(0, err))?;
+        self.process_loader_transaction(&create_buffer, &[&payer, &buffer])?;
+
+        let chunk_size = max_write_chunk_size(&payer.pubkey(), &buffer.pubkey());
+        for (index, chunk) in program.data.chunks(chunk_size).enumerate() {
+            let write = loader_instruction::write(
+                &buffer.pubkey(),
+                &payer.pubkey(),
+                (index * chunk_size) as u32,
+                chunk.to_vec(),
+            );
+            self.process_loader_transaction(&[write], &[&payer])?;
+        }
+
+        self.create_program_account(&program.program_id)?;
+
+        #[allow(deprecated)]
+        let deploy = loader_instruction::deploy_with_max_program_len(
+            &payer.pubkey(),
+            &program.program_id,
+            &buffer.pubkey(),
+            &payer.pubkey(),
+            0,
+            program_len,
+        )
+        .map_err(|err| TransactionError::InstructionError(0, err))?;
+        // The first instruction creates the program account, which requires its signature
+        self.process_loader_transaction(&deploy[1..], &[&payer])?;
+
+        if program.authority != Some(payer.pubkey()) {
+            let set_authority = loader_instruction::set_upgrade_authority(
+                &program.program_id,
+                &payer.pubkey(),
+                program.authority.as_ref(),
+            );
+            self.process_loader_transaction(&[set_authority], &[&payer])?;
+        }
+
+        // The deployed program becomes visible one slot after the deployment slot
+        self.advance_program_slot(self.accounts.get_sysvar::<Clock>().slot);
+        self.invalidate_program(&program.program_id);
+        Ok(())
+    }
+
+    /// Creates the uninitialized program account the way the system program would,
+    /// with the rent exempt minimum funded by the payer.
+    fn create_program_account(&mut self, program_id: &Pubkey) -> Result<(), TransactionError> {
+        let payer_pubkey = self.payer.pubkey();
+        let size = UpgradeableLoaderState::size_of_program();
+        let lamports = Rent::default().minimum_balance(size);
+
+        let mut payer_account = self
+            .get_account(&payer_pubkey)
+            .ok_or(TransactionError::AccountNotFound)?;
+        payer_account
+            .checked_sub_lamports(lamports)
+            .map_err(|_| TransactionError::InsufficientFundsForRent { account_index: 0 })?;
+        self.settle_accounts(&[(payer_pubkey, payer_account)]);
+
+        // zeroed data deserializes as `UpgradeableLoaderState::Uninitialized`
+        let program_account = AccountSharedData::new(
+            lamports,
+            size,
+            &solana_sdk_ids::bpf_loader_upgradeable::id(),
+        );
+        self.accounts.set_program(program_id, &program_account);
+        Ok(())
+    }
+
+    /// Processes a transaction signed by `signers` and stores the resulting accounts,
+    /// loader accounts are stored as programs.
+    fn process_loader_transaction(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            signers,
+            Hash::default(),
+        );
+        let result = self.process_transaction(transaction);
+
+        let executed_transaction = match &result.get_result().processing_results[0] {
+            Ok(ProcessedTransaction::Executed(executed_transaction)) => executed_transaction,
+            Ok(ProcessedTransaction::FeesOnly(fees_only)) => {
+                return Err(fees_only.load_error.clone())
+            }
+            Err(err) => return Err(err.clone()),
+        };
+        executed_transaction.execution_details.status.clone()?;
+
+        for (pubkey, account) in &executed_transaction.loaded_transaction.accounts {
+            if solana_sdk_ids::bpf_loader_upgradeable::check_id(account.owner()) {
+                if account.lamports() == 0 {
+                    self.accounts.remove_program(pubkey);
+                } else {
+                    self.accounts.set_program(pubkey, account);
+                }
+            } else {
+                self.settle_accounts(&[(*pubkey, account.clone())]);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Largest `Write` chunk which fits into a transaction, computed the same way the Solana CLI does.
+fn max_write_chunk_size(payer: &Pubkey, buffer: &Pubkey) -> usize {
+    let transaction = Transaction::new_with_payer(
+        &[loader_instruction::write(buffer, payer, 0, Vec::new())],
+        Some(payer),
+    );
+    let transaction_size =
+        bincode::serialized_size(&transaction).expect("Failed to serialize transaction") as usize;
+    // one more byte for the short vec encoding of the chunk length
+    PACKET_DATA_SIZE
+        .saturating_sub(transaction_size)
+        .saturating_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_account::ReadableAccount;
+    use solana_instruction::error::InstructionError;
+    use solana_loader_v3_interface::state::UpgradeableLoaderState;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+    use solana_transaction_error::TransactionError;
+
+    use crate::test_utils::initialize_mint_transaction;
+    use crate::trident_svm::TridentSVM;
+    use crate::types::trident_program::TridentProgram;
+
+    #[test]
+    fn test_deploy_program_with_loader() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let program_data = include_bytes!("../solana-program-library/spl-token-mainnet.so");
+        let mut builder = TridentSVM::builder();
+        builder.with_loader_deployment();
+        builder.with_sbf_programs(vec![TridentProgram::new(
+            program_id,
+            Some(authority),
+            program_data.to_vec(),
+        )]);
+        let mut svm = builder.build();
+        let payer = svm.get_payer();
+
+        let programdata_address = solana_loader_v3_interface::get_program_data_address(&program_id);
+        let programdata = svm.get_account(&programdata_address).unwrap();
+        assert_eq!(
+            bincode::deserialize::<UpgradeableLoaderState>(programdata.data()).unwrap(),
+            UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(authority),
+            }
+        );
+        assert_eq!(
+            &programdata.data()[UpgradeableLoaderState::size_of_programdata_metadata()..],
+            program_data
+        );
+        assert!(svm.get_account(&program_id).unwrap().executable());
+        assert!(
+            svm.get_account(&payer.pubkey()).unwrap().lamports()
+                < 500_000_000 * 1_000_000_000 - programdata.lamports()
+        );
+
+        let (_, transaction) = initialize_mint_transaction(&mut svm, &program_id);
+        let result = svm.process_transaction_with_settle(transaction);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_loader_deployment_keeps_seeded_keypairs() {
+        let build = |loader_deployment: bool| {
+            let mut builder = TridentSVM::builder();
+            builder.without_bundled_programs();
+            builder.with_seed(3);
+            if loader_deployment {
+                builder.with_loader_deployment();
+            }
+            builder.with_sbf_programs(vec![TridentProgram::new(
+                Pubkey::new_unique(),
+                None,
+                include_bytes!("../solana-program-library/spl-memo.so").to_vec(),
+            )]);
+            builder.build()
+        };
+
+        assert_eq!(
+            build(true).create_funded_keypair(1).pubkey(),
+            build(false).create_funded_keypair(1).pubkey()
+        );
+    }
+
+    #[test]
+    fn test_loader_rejects_invalid_elf() {
+        let mut svm = TridentSVM::default();
+        let program = TridentProgram::new(Pubkey::new_unique(), None, vec![0; 1024]);
+
+        assert_eq!(
+            svm.deploy_program_with_loader(&program),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::InvalidAccountData
+            ))
+        );
+    }
+}
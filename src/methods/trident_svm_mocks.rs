@@ -0,0 +1,279 @@
+use std::sync::Arc;
+
+use solana_instruction::error::InstructionError;
+use solana_program_runtime::loaded_programs::ProgramCacheEntry;
+use solana_pubkey::Pubkey;
+
+use crate::mocking::mock_entrypoint;
+use crate::mocking::MockedProgram;
+use crate::mocking::MOCK_PROGRAM_NAME;
+use crate::trident_svm::TridentSVM;
+use crate::types::program_mock::MockInvocation;
+use crate::utils::create_loadable_account_for_test;
+
+impl TridentSVM {
+    /// Executes the closure instead of the program, both for top level instructions and CPIs.
+    ///
+    /// The program does not have to be deployed. Mocking an already mocked program replaces
+    /// the closure, [`TridentSVM::unmock_program`] restores the original program. An instance
+    /// sharing the program cache with workers gets its own cache, the workers keep executing
+    /// the original program.
+    ///
+    /// # Panics
+    ///
+    /// Panics if transactions are recorded, see [`TridentSVM::start_transaction_recording`].
+    pub fn mock_program<F>(&mut self, program_id: &Pubkey, mock: F)
+    where
+        F: Fn(&mut MockInvocation) -> Result<(), InstructionError> + Send + Sync + 'static,
+    {
+        assert!(
+            self.transaction_recording.is_none(),
+            "Programs cannot be mocked while transactions are recorded"
+        );
+        if let Some(mocked) = self.mocks.get_mut(program_id) {
+            mocked.mock = Arc::new(mock);
+            return;
+        }
+
+        if self.is_program_cache_shared() {
+            self.rebuild_program_cache();
+        }
+
+        let original_account = self.accounts.get_program(program_id);
+        // builtins are kept to be added again, SBF programs are compiled again on unmocking
+        let original_builtin = self.builtins.get(program_id).cloned();
+        self.processor
+            .program_cache
+            .write()
+            .expect("Failed to write to program cache")
+            .remove_programs(std::iter::once(*program_id));
+
+        // only programs owned by the native loader are executed by their own builtin
+        self.accounts.set_program(
+            program_id,
+            &create_loadable_account_for_test(MOCK_PROGRAM_NAME),
+        );
+        self.add_builtin(
+            *program_id,
+            ProgramCacheEntry::new_builtin(0, MOCK_PROGRAM_NAME.len(), mock_entrypoint),
+        );
+
+        self.mocks.insert(
+            *program_id,
+            MockedProgram {
+                mock: Arc::new(mock),
+                original_account,
+                original_builtin,
+            },
+        );
+    }
+
+    /// Removes the mock of the program and restores the original program, if any.
+    pub fn unmock_program(&mut self, program_id: &Pubkey) {
+        let Some(mocked) = self.mocks.remove(program_id) else {
+            return;
+        };
+        if self.is_program_cache_shared() {
+            self.rebuild_program_cache();
+        }
+
+        self.processor
+            .program_cache
+            .write()
+            .expect("Failed to write to program cache")
+            .remove_programs(std::iter::once(*program_id));
+
+        match &mocked.original_account {
+            Some(account) => self.accounts.set_program(program_id, account),
+            None => self.accounts.remove_program(program_id),
+        }
+
+        match mocked.original_builtin {
+            Some(entry) => {
+                self.processor
+                    .program_cache
+                    .write()
+                    .expect("Failed to write to program cache")
+                    .assign_program(*program_id, Arc::clone(&entry));
+                self.builtins.insert(*program_id, entry);
+            }
+            None => {
+                self.builtins.remove(program_id);
+                self.processor
+                    .builtin_program_ids
+                    .write()
+                    .expect("Failed to write builtin program ids")
+                    .remove(program_id);
+                // SBF programs are compiled for the current environment
+                self.invalidate_program(program_id);
+            }
+        }
+    }
+
+    /// Whether the program is replaced by a mock.
+    pub fn is_mocked(&self, program_id: &Pubkey) -> bool {
+        self.mocks.contains_key(program_id)
+    }
+}
+
+#[cfg(all(test, feature = "spl-token", feature = "spl-associated-token-account"))]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use solana_account::ReadableAccount;
+    use solana_hash::Hash;
+    use solana_instruction::error::InstructionError;
+    use solana_instruction::AccountMeta;
+    use solana_instruction::Instruction;
+    use solana_keypair::Keypair;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+    use solana_transaction::Transaction;
+    use solana_transaction_error::TransactionError;
+
+    use crate::trident_svm::TridentSVM;
+    use crate::types::bundled_program::BundledProgram;
+    use crate::types::token_state::TokenAccount;
+
+    /// Creates the associated token account of a new wallet, the associated token
+    /// program invokes the token program through CPI.
+    fn create_associated_token_account(
+        svm: &mut TridentSVM,
+        mint: &Pubkey,
+    ) -> (Pubkey, Result<(), TransactionError>) {
+        let token_program = BundledProgram::SplToken.program_id();
+        let payer = svm.get_payer();
+        let wallet = Keypair::new().pubkey();
+        let address = TokenAccount::associated_address(&wallet, mint, &token_program);
+
+        let instruction = Instruction::new_with_bytes(
+            BundledProgram::AssociatedToken.program_id(),
+            &[0],
+            vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(address, false),
+                AccountMeta::new_readonly(wallet, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(solana_sdk_ids::system_program::id(), false),
+                AccountMeta::new_readonly(token_program, false),
+            ],
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        let result = svm.process_transaction_with_settle(transaction);
+        let result = result.get_result().processing_results[0].flattened_result();
+        (address, result)
+    }
+
+    #[test]
+    fn test_mock_cpi_target() {
+        let mut svm = TridentSVM::default();
+        let token_program = BundledProgram::SplToken.program_id();
+        let mint = Keypair::new().pubkey();
+        svm.create_mint(&mint, &Pubkey::new_unique(), 6, &token_program);
+
+        // records the invoked instructions and answers the account size query
+        let invoked = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&invoked);
+        svm.mock_program(&token_program, move |invocation| {
+            assert_eq!(invocation.stack_height, 2);
+            recorded.lock().unwrap().push(invocation.data[0]);
+            if invocation.data[0] == 21 {
+                invocation.set_return_data(&165u64.to_le_bytes());
+            }
+            Ok(())
+        });
+        assert!(svm.is_mocked(&token_program));
+
+        let (address, result) = create_associated_token_account(&mut svm, &mint);
+        assert_eq!(result, Ok(()));
+        // GetAccountDataSize, InitializeImmutableOwner and InitializeAccount3
+        assert_eq!(*invoked.lock().unwrap(), vec![21, 22, 18]);
+        // the account was allocated by the system program but never initialized
+        let account = svm.get_account(&address).unwrap();
+        assert_eq!(account.data(), &[0; 165]);
+
+        svm.mock_program(&token_program, |_| Err(InstructionError::Custom(7)));
+        let (_, result) = create_associated_token_account(&mut svm, &mint);
+        assert_eq!(
+            result,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(7)
+            ))
+        );
+
+        svm.unmock_program(&token_program);
+        assert!(!svm.is_mocked(&token_program));
+        let (address, result) = create_associated_token_account(&mut svm, &mint);
+        assert_eq!(result, Ok(()));
+        assert_eq!(svm.get_token_account(&address).unwrap().mint, mint);
+    }
+
+    #[test]
+    fn test_mock_builtin() {
+        let mut svm = TridentSVM::default();
+        let token_program = BundledProgram::SplToken.program_id();
+        let system_program = solana_sdk_ids::system_program::id();
+        let mint = Keypair::new().pubkey();
+        svm.create_mint(&mint, &Pubkey::new_unique(), 6, &token_program);
+
+        svm.mock_program(&system_program, |_| Err(InstructionError::Custom(3)));
+        let (_, result) = create_associated_token_account(&mut svm, &mint);
+        assert_eq!(
+            result,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(3)
+            ))
+        );
+
+        // the replaced builtin is executed again
+        svm.unmock_program(&system_program);
+        let (address, result) = create_associated_token_account(&mut svm, &mint);
+        assert_eq!(result, Ok(()));
+        assert_eq!(svm.get_token_account(&address).unwrap().mint, mint);
+    }
+
+    #[test]
+    fn test_mocks_are_not_shared_with_workers() {
+        let mut svm = TridentSVM::default();
+        let token_program = BundledProgram::SplToken.program_id();
+        let mint = Keypair::new().pubkey();
+        svm.create_mint(&mint, &Pubkey::new_unique(), 6, &token_program);
+        let mut worker = svm.new_worker();
+
+        svm.mock_program(&token_program, |_| Err(InstructionError::Custom(7)));
+        let (_, result) = create_associated_token_account(&mut svm, &mint);
+        assert_eq!(
+            result,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(7)
+            ))
+        );
+        let (address, result) = create_associated_token_account(&mut worker, &mint);
+        assert_eq!(result, Ok(()));
+        assert_eq!(worker.get_token_account(&address).unwrap().mint, mint);
+
+        // a worker of the mocking instance unmocks only its own cache
+        let mut mocked_worker = svm.new_worker();
+        mocked_worker.unmock_program(&token_program);
+        let (_, result) = create_associated_token_account(&mut svm, &mint);
+        assert_eq!(
+            result,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(7)
+            ))
+        );
+        let (_, result) = create_associated_token_account(&mut mocked_worker, &mint);
+        assert_eq!(result, Ok(()));
+    }
+}
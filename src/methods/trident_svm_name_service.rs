@@ -0,0 +1,70 @@
+use solana_account::ReadableAccount;
+use solana_pubkey::Pubkey;
+
+use crate::trident_svm::TridentSVM;
+use crate::types::name_record::NameRecord;
+use crate::types::name_record::NAME_SERVICE_PROGRAM_ID;
+
+impl TridentSVM {
+    /// Stores the record at the address the Name Service program derives for the name,
+    /// class and parent of the record.
+    pub fn create_name_record(&mut self, name: &str, record: &NameRecord) -> Pubkey {
+        let class = (record.class != Pubkey::default()).then_some(&record.class);
+        let parent_name = (record.parent_name != Pubkey::default()).then_some(&record.parent_name);
+        let address = NameRecord::address(name, class, parent_name);
+
+        let data = record.serialize();
+        self.set_rent_exempt_account(&address, &data, &NAME_SERVICE_PROGRAM_ID, false);
+        address
+    }
+
+    pub fn get_name_record(&self, address: &Pubkey) -> Option<NameRecord> {
+        let account = self.get_account(address)?;
+        if account.owner() != &NAME_SERVICE_PROGRAM_ID {
+            return None;
+        }
+        NameRecord::deserialize(account.data())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_account::ReadableAccount;
+    use solana_pubkey::Pubkey;
+
+    use crate::trident_svm::TridentSVM;
+    use crate::types::name_record::NameRecord;
+    use crate::types::name_record::NAME_SERVICE_PROGRAM_ID;
+
+    #[test]
+    fn test_name_records() {
+        let mut svm = TridentSVM::builder().without_bundled_programs().build();
+        let parent = NameRecord {
+            owner: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        let parent_address = svm.create_name_record("sol", &parent);
+        assert_eq!(parent_address, NameRecord::address("sol", None, None));
+
+        let record = NameRecord {
+            parent_name: parent_address,
+            owner: Pubkey::new_unique(),
+            data: b"trident".to_vec(),
+            ..Default::default()
+        };
+        let address = svm.create_name_record("ackee", &record);
+        assert_eq!(
+            address,
+            NameRecord::address("ackee", None, Some(&parent_address))
+        );
+        assert_ne!(address, NameRecord::address("ackee", None, None));
+
+        let account = svm.get_account(&address).unwrap();
+        assert_eq!(account.owner(), &NAME_SERVICE_PROGRAM_ID);
+        // the header holds the parent, the owner and the class
+        assert_eq!(account.data().len(), 96 + 7);
+        assert_eq!(account.data()[..32], parent_address.to_bytes());
+        assert_eq!(svm.get_name_record(&address), Some(record));
+        assert_eq!(svm.get_name_record(&parent_address), Some(parent));
+    }
+}
@@ -0,0 +1,278 @@
+use solana_account::ReadableAccount;
+use solana_pubkey::Pubkey;
+
+use crate::trident_svm::TridentSVM;
+use crate::types::bundled_program::BundledProgram;
+use crate::types::nft_metadata::serialize_master_edition;
+use crate::types::nft_metadata::serialize_token_record;
+use crate::types::nft_metadata::MintedNft;
+use crate::types::nft_metadata::NftMetadata;
+use crate::types::nft_metadata::TokenStandard;
+use crate::types::token_state::TokenAccount;
+use crate::types::token_state::TokenMint;
+
+impl TridentSVM {
+    /// Stores the metadata at the address derived from its mint into the permanent store.
+    pub fn set_nft_metadata(&mut self, metadata: &NftMetadata) {
+        self.set_rent_exempt_account(
+            &NftMetadata::address(&metadata.mint),
+            &metadata.serialize(),
+            &BundledProgram::TokenMetadata.program_id(),
+            true,
+        );
+    }
+
+    pub fn get_nft_metadata(&self, mint: &Pubkey) -> Option<NftMetadata> {
+        let account = self.get_account(&NftMetadata::address(mint))?;
+        if account.owner() != &BundledProgram::TokenMetadata.program_id() {
+            return None;
+        }
+        NftMetadata::deserialize(account.data())
+    }
+
+    /// Mints the NFT described by the metadata to the associated token account of the owner,
+    /// as Token Metadata would after creating the metadata and the master edition.
+    ///
+    /// The mint is owned by `token_program`, either SPL Token or Token 2022. The mint and
+    /// freeze authority of the mint is the master edition, which has no prints. Programmable NFTs get a frozen token account and an unlocked token record.
+    /// A collection NFT is minted the same way with `collection_size` set. All accounts are
+    /// written into the permanent store.
+    pub fn mint_nft(
+        &mut self,
+        owner: &Pubkey,
+        metadata: &NftMetadata,
+        token_program: &Pubkey,
+    ) -> MintedNft {
+        let mint = metadata.mint;
+        let (master_edition, edition_bump) = NftMetadata::master_edition_address(&mint);
+        let token_standard = metadata
+            .token_standard
+            .unwrap_or(TokenStandard::NonFungible);
+        let programmable = token_standard.is_programmable();
+
+        let mint_state = TokenMint {
+            mint_authority: Some(master_edition),
+            supply: 1,
+            decimals: 0,
+            freeze_authority: Some(master_edition),
+            extensions: vec![],
+        };
+        self.set_rent_exempt_account(&mint, &mint_state.serialize(), token_program, true);
+
+        let token_account = TokenAccount::associated_address(owner, &mint, token_program);
+        let token_account_state = TokenAccount {
+            mint,
+            owner: *owner,
+            amount: 1,
+            is_frozen: programmable,
+            ..Default::default()
+        };
+        self.set_rent_exempt_account(
+            &token_account,
+            &token_account_state.serialize(),
+            token_program,
+            true,
+        );
+
+        self.set_nft_metadata(&NftMetadata {
+            edition_nonce: Some(edition_bump),
+            token_standard: Some(token_standard),
+            ..metadata.clone()
+        });
+        self.set_rent_exempt_account(
+            &master_edition,
+            &serialize_master_edition(Some(0)),
+            &BundledProgram::TokenMetadata.program_id(),
+            true,
+        );
+
+        let token_record = programmable.then(|| {
+            let (token_record, bump) = NftMetadata::token_record_address(&mint, &token_account);
+            self.set_rent_exempt_account(
+                &token_record,
+                &serialize_token_record(bump),
+                &BundledProgram::TokenMetadata.program_id(),
+                true,
+            );
+            token_record
+        });
+
+        MintedNft {
+            mint,
+            token_account,
+            metadata: NftMetadata::address(&mint),
+            master_edition,
+            token_record,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mpl-token-metadata", feature = "spl-token"))]
+mod tests {
+    use solana_account::ReadableAccount;
+    use solana_hash::Hash;
+    use solana_instruction::AccountMeta;
+    use solana_instruction::Instruction;
+    use solana_keypair::Keypair;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+    use solana_transaction::Transaction;
+
+    use crate::trident_svm::TridentSVM;
+    use crate::types::bundled_program::BundledProgram;
+    use crate::types::nft_metadata::NftCollection;
+    use crate::types::nft_metadata::NftCreator;
+    use crate::types::nft_metadata::NftMetadata;
+    use crate::types::nft_metadata::TokenStandard;
+    use crate::types::token_state::TokenAccount;
+
+    fn process(svm: &mut TridentSVM, instruction: Instruction, signer: &Keypair) -> bool {
+        let payer = svm.get_payer();
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer, signer],
+            Hash::default(),
+        );
+        let result = svm.process_transaction_with_settle(transaction);
+        result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_mint_programmable_nft_into_collection() {
+        let mut svm = TridentSVM::default();
+        let token_metadata = BundledProgram::TokenMetadata.program_id();
+        let token_program = BundledProgram::SplToken.program_id();
+        let authority = Keypair::new();
+        let owner = Keypair::new();
+
+        let collection = svm.mint_nft(
+            &authority.pubkey(),
+            &NftMetadata {
+                update_authority: authority.pubkey(),
+                mint: Pubkey::new_unique(),
+                name: "Collection".to_string(),
+                collection_size: Some(0),
+                ..Default::default()
+            },
+            &token_program,
+        );
+        let metadata = NftMetadata {
+            update_authority: authority.pubkey(),
+            mint: Pubkey::new_unique(),
+            name: "Trident #1".to_string(),
+            symbol: "TRD".to_string(),
+            uri: "https://example.com/1.json".to_string(),
+            seller_fee_basis_points: 500,
+            creators: Some(vec![NftCreator {
+                address: authority.pubkey(),
+                verified: true,
+                share: 100,
+            }]),
+            is_mutable: true,
+            token_standard: Some(TokenStandard::ProgrammableNonFungible),
+            collection: Some(NftCollection {
+                verified: false,
+                key: collection.mint,
+            }),
+            rule_set: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        let nft = svm.mint_nft(&owner.pubkey(), &metadata, &token_program);
+        assert!(nft.token_record.is_some());
+        assert_eq!(
+            svm.get_nft_metadata(&nft.mint).unwrap(),
+            NftMetadata {
+                edition_nonce: Some(NftMetadata::master_edition_address(&nft.mint).1),
+                ..metadata
+            }
+        );
+
+        // Verify with VerificationArgs::CollectionV1, missing optional accounts are the program id
+        let verify = Instruction::new_with_bytes(
+            token_metadata,
+            &[52, 1],
+            vec![
+                AccountMeta::new_readonly(authority.pubkey(), true),
+                AccountMeta::new_readonly(token_metadata, false),
+                AccountMeta::new(nft.metadata, false),
+                AccountMeta::new_readonly(collection.mint, false),
+                AccountMeta::new(collection.metadata, false),
+                AccountMeta::new_readonly(collection.master_edition, false),
+                AccountMeta::new_readonly(solana_sdk_ids::system_program::id(), false),
+                AccountMeta::new_readonly(solana_sdk_ids::sysvar::instructions::id(), false),
+            ],
+        );
+        assert!(process(&mut svm, verify, &authority));
+        assert!(
+            svm.get_nft_metadata(&nft.mint)
+                .unwrap()
+                .collection
+                .unwrap()
+                .verified
+        );
+        assert_eq!(
+            svm.get_nft_metadata(&collection.mint)
+                .unwrap()
+                .collection_size,
+            Some(1)
+        );
+
+        // UpdatePrimarySaleHappenedViaToken checks the token account of the owner, it is not
+        // supported for programmable NFTs
+        let nft = svm.mint_nft(
+            &owner.pubkey(),
+            &NftMetadata {
+                update_authority: authority.pubkey(),
+                mint: Pubkey::new_unique(),
+                name: "Trident #2".to_string(),
+                ..Default::default()
+            },
+            &token_program,
+        );
+        let primary_sale = Instruction::new_with_bytes(
+            token_metadata,
+            &[4],
+            vec![
+                AccountMeta::new(nft.metadata, false),
+                AccountMeta::new_readonly(owner.pubkey(), true),
+                AccountMeta::new_readonly(nft.token_account, false),
+            ],
+        );
+        assert!(process(&mut svm, primary_sale, &owner));
+        assert!(
+            svm.get_nft_metadata(&nft.mint)
+                .unwrap()
+                .primary_sale_happened
+        );
+    }
+
+    #[test]
+    fn test_mint_token_2022_programmable_nft() {
+        let mut svm = TridentSVM::default();
+        let token_2022 = BundledProgram::SplToken2022.program_id();
+        let owner = Pubkey::new_unique();
+
+        let nft = svm.mint_nft(
+            &owner,
+            &NftMetadata {
+                mint: Pubkey::new_unique(),
+                name: "Trident 2022".to_string(),
+                token_standard: Some(TokenStandard::ProgrammableNonFungible),
+                ..Default::default()
+            },
+            &token_2022,
+        );
+        assert_eq!(
+            nft.token_account,
+            TokenAccount::associated_address(&owner, &nft.mint, &token_2022)
+        );
+        assert_eq!(svm.get_account(&nft.mint).unwrap().owner(), &token_2022);
+        let token_account = svm.get_token_account(&nft.token_account).unwrap();
+        assert_eq!((token_account.amount, token_account.is_frozen), (1, true));
+        assert!(nft.token_record.is_some());
+    }
+}
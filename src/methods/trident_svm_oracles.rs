@@ -0,0 +1,285 @@
+use solana_account::ReadableAccount;
+use solana_clock::Clock;
+use solana_pubkey::Pubkey;
+
+use crate::trident_svm::TridentSVM;
+use crate::types::bundled_program::BundledProgram;
+use crate::types::chainlink_feed::ChainlinkFeed;
+use crate::types::chainlink_feed::ChainlinkRound;
+use crate::types::pyth_price_update::PythPriceUpdate;
+use crate::types::pyth_price_update::PYTH_RECEIVER_PROGRAM_ID;
+
+/// Rounds kept in the live buffer of feeds updated through [`TridentSVM::update_chainlink_price`].
+const CHAINLINK_LIVE_ROUNDS: usize = 64;
+
+impl TridentSVM {
+    /// Stores the feed owned by the bundled Chainlink store program.
+    ///
+    /// Round ids have to be consecutive, only the id of the latest round is stored.
+    pub fn set_chainlink_feed(&mut self, address: &Pubkey, feed: &ChainlinkFeed) {
+        self.set_rent_exempt_account(
+            address,
+            &feed.serialize(),
+            &BundledProgram::ChainlinkOracle.program_id(),
+            false,
+        );
+    }
+
+    pub fn get_chainlink_feed(&self, address: &Pubkey) -> Option<ChainlinkFeed> {
+        let account = self.get_account(address)?;
+        if account.owner() != &BundledProgram::ChainlinkOracle.program_id() {
+            return None;
+        }
+        ChainlinkFeed::deserialize(account.data())
+    }
+
+    /// Creates a feed without any round, e.g. `create_chainlink_feed(&feed, "SOL / USD", 8)`.
+    pub fn create_chainlink_feed(&mut self, address: &Pubkey, description: &str, decimals: u8) {
+        self.set_chainlink_feed(
+            address,
+            &ChainlinkFeed {
+                description: description.to_string(),
+                decimals,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Submits a new round with the slot and timestamp of the current `Clock`. Feeds
+    /// store 32-bit timestamps, timestamps outside of their range are clamped.
+    ///
+    /// Panics if the account is not a Chainlink feed.
+    pub fn update_chainlink_price(&mut self, address: &Pubkey, answer: i128) {
+        let mut feed = self
+            .get_chainlink_feed(address)
+            .expect("Account is not a Chainlink feed");
+        let clock = self.get_sysvar::<Clock>();
+        let round_id = feed.latest_round().map_or(0, |round| round.round_id) + 1;
+        let timestamp =
+            u32::try_from(clock.unix_timestamp).unwrap_or(if clock.unix_timestamp < 0 {
+                0
+            } else {
+                u32::MAX
+            });
+        feed.rounds.push(ChainlinkRound {
+            round_id,
+            slot: clock.slot,
+            timestamp,
+            answer,
+        });
+        if feed.rounds.len() > CHAINLINK_LIVE_ROUNDS {
+            feed.rounds.remove(0);
+        }
+        self.set_chainlink_feed(address, &feed);
+    }
+
+    /// Stores the price update owned by the Pyth receiver program.
+    pub fn set_pyth_price_update_account(
+        &mut self,
+        address: &Pubkey,
+        price_update: &PythPriceUpdate,
+    ) {
+        self.set_rent_exempt_account(
+            address,
+            &price_update.serialize(),
+            &PYTH_RECEIVER_PROGRAM_ID,
+            false,
+        );
+    }
+
+    pub fn get_pyth_price_update(&self, address: &Pubkey) -> Option<PythPriceUpdate> {
+        let account = self.get_account(address)?;
+        if account.owner() != &PYTH_RECEIVER_PROGRAM_ID {
+            return None;
+        }
+        PythPriceUpdate::deserialize(account.data())
+    }
+
+    /// Stores a fully verified Pyth price update published in the current slot.
+    ///
+    /// The EMA price and confidence equal the price and confidence.
+    pub fn set_pyth_price_update(
+        &mut self,
+        address: &Pubkey,
+        feed_id: [u8; 32],
+        price: i64,
+        conf: u64,
+        exponent: i32,
+    ) {
+        let clock = self.get_sysvar::<Clock>();
+        self.set_pyth_price_update_account(
+            address,
+            &PythPriceUpdate {
+                feed_id,
+                price,
+                conf,
+                exponent,
+                publish_time: clock.unix_timestamp,
+                prev_publish_time: clock.unix_timestamp,
+                ema_price: price,
+                ema_conf: conf,
+                posted_slot: clock.slot,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Publishes a new price with the timestamp and slot of the current `Clock`, the EMA
+    /// price and confidence are kept.
+    ///
+    /// Panics if the account is not a Pyth price update.
+    pub fn update_pyth_price(&mut self, address: &Pubkey, price: i64, conf: u64) {
+        let price_update = self
+            .get_pyth_price_update(address)
+            .expect("Account is not a Pyth price update");
+        let clock = self.get_sysvar::<Clock>();
+        self.set_pyth_price_update_account(
+            address,
+            &PythPriceUpdate {
+                price,
+                conf,
+                prev_publish_time: price_update.publish_time,
+                publish_time: clock.unix_timestamp,
+                posted_slot: clock.slot,
+                ..price_update
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_account::ReadableAccount;
+    use solana_clock::Clock;
+    use solana_pubkey::Pubkey;
+
+    use crate::trident_svm::TridentSVM;
+    use crate::types::pyth_price_update::PYTH_RECEIVER_PROGRAM_ID;
+
+    /// Anchor discriminator of the `query` instruction of the Chainlink store program.
+    #[cfg(feature = "chainlink-oracle")]
+    const QUERY: [u8; 8] = [39, 251, 130, 159, 46, 136, 164, 169];
+
+    #[cfg(feature = "chainlink-oracle")]
+    fn query(svm: &mut TridentSVM, feed: &Pubkey, query: &[u8]) -> Vec<u8> {
+        use solana_hash::Hash;
+        use solana_instruction::AccountMeta;
+        use solana_instruction::Instruction;
+        use solana_signer::Signer;
+        use solana_svm::transaction_processing_result::ProcessedTransaction;
+        use solana_transaction::Transaction;
+
+        use crate::types::bundled_program::BundledProgram;
+
+        let payer = svm.get_payer();
+        let instruction = Instruction::new_with_bytes(
+            BundledProgram::ChainlinkOracle.program_id(),
+            &[&QUERY[..], query].concat(),
+            vec![AccountMeta::new_readonly(*feed, false)],
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        let result = svm.process_transaction_with_settle(transaction);
+        match &result.get_result().processing_results[0] {
+            Ok(ProcessedTransaction::Executed(executed)) => {
+                assert!(executed.execution_details.status.is_ok());
+                executed
+                    .execution_details
+                    .return_data
+                    .as_ref()
+                    .unwrap()
+                    .data
+                    .clone()
+            }
+            _ => panic!("Query was not executed"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "chainlink-oracle")]
+    fn test_chainlink_price_updates() {
+        let mut svm = TridentSVM::default();
+        let feed = Pubkey::new_unique();
+        svm.create_chainlink_feed(&feed, "SOL / USD", 8);
+        svm.update_chainlink_price(&feed, 150_00000000);
+        svm.set_sysvar(&Clock {
+            slot: 10,
+            unix_timestamp: 1_700_000_000,
+            ..svm.get_sysvar::<Clock>()
+        });
+        svm.update_chainlink_price(&feed, 140_00000000);
+
+        // Query::Decimals
+        assert_eq!(query(&mut svm, &feed, &[1]), [8]);
+
+        // Query::LatestRoundData returns the round id, slot, timestamp and answer
+        let mut latest_round = 2u32.to_le_bytes().to_vec();
+        latest_round.extend_from_slice(&10u64.to_le_bytes());
+        latest_round.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        latest_round.extend_from_slice(&140_00000000i128.to_le_bytes());
+        assert_eq!(query(&mut svm, &feed, &[4]), latest_round);
+
+        // Query::RoundData of the first round
+        let round_data = query(&mut svm, &feed, &[3, 1, 0, 0, 0]);
+        assert_eq!(round_data[..4], 1u32.to_le_bytes());
+        assert_eq!(round_data[16..], 150_00000000i128.to_le_bytes());
+
+        let rounds = svm.get_chainlink_feed(&feed).unwrap().rounds;
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].answer, 150_00000000);
+
+        // timestamps past 2106 do not fit the feed
+        svm.set_sysvar(&Clock {
+            unix_timestamp: i64::from(u32::MAX) + 1,
+            ..svm.get_sysvar::<Clock>()
+        });
+        svm.update_chainlink_price(&feed, 130_00000000);
+        let feed = svm.get_chainlink_feed(&feed).unwrap();
+        assert_eq!(feed.latest_round().unwrap().timestamp, u32::MAX);
+    }
+
+    #[test]
+    fn test_pyth_price_updates() {
+        let mut svm = TridentSVM::builder().without_bundled_programs().build();
+        let price_update = Pubkey::new_unique();
+        svm.set_pyth_price_update(&price_update, [7; 32], 150_000, 100, -3);
+        svm.set_sysvar(&Clock {
+            slot: 10,
+            unix_timestamp: 1_700_000_000,
+            ..svm.get_sysvar::<Clock>()
+        });
+        svm.update_pyth_price(&price_update, 140_000, 200);
+
+        let price = svm.get_pyth_price_update(&price_update).unwrap();
+        assert_eq!(price.feed_id, [7; 32]);
+        assert_eq!(
+            (price.price, price.conf, price.exponent),
+            (140_000, 200, -3)
+        );
+        assert_eq!(price.ema_price, 150_000);
+        assert_eq!(price.publish_time, 1_700_000_000);
+        assert_eq!(price.posted_slot, 10);
+    }
+
+    #[test]
+    fn test_pyth_price_update_layout() {
+        let mut svm = TridentSVM::builder().without_bundled_programs().build();
+        let price_update = Pubkey::new_unique();
+        svm.set_pyth_price_update(&price_update, [7; 32], 150_000, 100, -3);
+
+        let account = svm.get_account(&price_update).unwrap();
+        assert_eq!(account.owner(), &PYTH_RECEIVER_PROGRAM_ID);
+        assert_eq!(account.data().len(), 134);
+        // discriminator, write authority, full verification and the feed id
+        assert_eq!(account.data()[40], 1);
+        assert_eq!(account.data()[41..73], [7; 32]);
+        assert_eq!(account.data()[73..81], 150_000i64.to_le_bytes());
+        assert_eq!(account.data()[81..89], 100u64.to_le_bytes());
+        assert_eq!(account.data()[89..93], (-3i32).to_le_bytes());
+        assert!(svm.get_pyth_price_update(&Pubkey::new_unique()).is_none());
+    }
+}
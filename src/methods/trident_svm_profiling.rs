@@ -0,0 +1,76 @@
+use crate::instrumentation::profiler::ComputeUnitProfiler;
+use crate::instrumentation::ExecutionRecord;
+use crate::trident_svm::TridentSVM;
+use crate::types::compute_unit_profile::ComputeUnitProfile;
+
+impl TridentSVM {
+    pub(crate) fn enable_compute_unit_profiling(&mut self) {
+        self.cu_profiler = Some(ComputeUnitProfiler::default());
+        self.instrument_program_execution();
+    }
+
+    /// Compute units accumulated from executed programs, `None` if profiling is not enabled.
+    pub fn get_compute_unit_profile(&self) -> Option<&ComputeUnitProfile> {
+        self.cu_profiler.as_ref().map(|profiler| &profiler.profile)
+    }
+
+    /// Returns the accumulated compute unit profile and starts profiling from scratch.
+    pub fn take_compute_unit_profile(&mut self) -> Option<ComputeUnitProfile> {
+        self.cu_profiler
+            .as_mut()
+            .map(|profiler| std::mem::take(&mut profiler.profile))
+    }
+
+    pub(crate) fn record_compute_units(&mut self, record: &ExecutionRecord) {
+        if let Some(mut profiler) = self.cu_profiler.take() {
+            profiler.record(record, |program_id| self.get_program_elf(program_id));
+            self.cu_profiler = Some(profiler);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "spl-token"))]
+mod tests {
+    use solana_pubkey::pubkey;
+
+    use crate::test_utils::initialize_mint_transaction;
+    use crate::trident_svm::TridentSVM;
+
+    #[test]
+    fn test_compute_unit_profile_of_spl_token() {
+        let token_program = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+        let mut svm = TridentSVM::builder().with_compute_unit_profiling().build();
+        let (_, transaction) = initialize_mint_transaction(&mut svm, &token_program);
+
+        let result = svm.process_transaction_with_settle(transaction);
+        let executed = result.get_result().processing_results[0]
+            .as_ref()
+            .unwrap()
+            .executed_transaction()
+            .unwrap();
+        assert!(executed.execution_details.status.is_ok());
+
+        let profile = svm.take_compute_unit_profile().unwrap();
+        let token_units = profile.program(&token_program).unwrap();
+        assert_eq!(token_units.invocations, 1);
+        assert_eq!(token_units.units, executed.execution_details.executed_units);
+        assert_eq!(token_units.units, token_units.self_units);
+
+        // spl token logs the processed instruction
+        assert!(profile
+            .syscalls()
+            .any(|(name, units)| name == "sol_log_" && units.calls == 1));
+        assert_eq!(profile.cpis().count(), 0);
+
+        // every consumed unit is attributed to exactly one stack
+        let stacks = profile.folded_stacks();
+        assert_eq!(stacks.values().sum::<u64>(), token_units.units);
+        assert!(stacks
+            .keys()
+            .all(|stack| stack.starts_with(&token_program.to_string())));
+        assert!(stacks.keys().any(|stack| stack.ends_with(";sol_log_")));
+        assert!(!profile.functions(&token_program).is_empty());
+
+        assert!(svm.get_compute_unit_profile().unwrap().is_empty());
+    }
+}
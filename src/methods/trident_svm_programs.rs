@@ -1,18 +1,49 @@
+use std::sync::Arc;
+
 use solana_account::AccountSharedData;
+use solana_account::ReadableAccount;
 use solana_account::WritableAccount;
+use solana_clock::Clock;
+use solana_instruction::error::InstructionError;
 use solana_loader_v3_interface::state::UpgradeableLoaderState;
+use solana_loader_v4_interface::state::LoaderV4State;
+use solana_loader_v4_interface::state::LoaderV4Status;
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_program_runtime::solana_sbpf::elf::Executable;
+use solana_program_runtime::solana_sbpf::verifier::RequisiteVerifier;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
 use solana_sysvar::rent::Rent;
 
 #[cfg(feature = "syscall-v2")]
-use crate::types::trident_entrypoint::TridentEntrypoint;
+use crate::builtin_function::native_entrypoint;
+#[cfg(feature = "syscall-v2")]
+use crate::instrumentation::native::instrumented_native_program;
+#[cfg(feature = "syscall-v2")]
+use crate::types::trident_entrypoint::ProgramEntry;
 #[cfg(feature = "syscall-v2")]
+use crate::types::trident_entrypoint::TridentEntrypoint;
 use solana_program_runtime::loaded_programs::ProgramCacheEntry;
 
+use crate::program_cache::compiled_program;
 use crate::trident_svm::TridentSVM;
+use crate::types::trident_program::ProgramLoader;
 use crate::types::trident_program::TridentProgram;
 
 impl TridentSVM {
     pub fn deploy_binary_program(&mut self, program: &TridentProgram) {
+        match program.loader {
+            ProgramLoader::Upgradeable => self.deploy_upgradeable_program(program),
+            ProgramLoader::NonUpgradeable | ProgramLoader::Deprecated => {
+                self.deploy_non_upgradeable_program(program)
+            }
+            ProgramLoader::V4 => self.deploy_loader_v4_program(program),
+        }
+
+        self.invalidate_program(&program.program_id);
+    }
+
+    fn deploy_upgradeable_program(&mut self, program: &TridentProgram) {
         let rent = Rent::default();
 
         let program_account = &program.program_id;
@@ -65,12 +96,271 @@ impl TridentSVM {
             .set_program(&program_data_account, &account_data);
     }
 
+    /// The program account of `bpf_loader` and `bpf_loader_deprecated` programs holds the ELF.
+    fn deploy_non_upgradeable_program(&mut self, program: &TridentProgram) {
+        let account_data = AccountSharedData::create(
+            Rent::default().minimum_balance(program.data.len()),
+            program.data.clone(),
+            program.loader.id(),
+            true,
+            Default::default(),
+        );
+
+        self.accounts
+            .set_program(&program.program_id, &account_data);
+    }
+
+    /// The program account of loader-v4 programs holds the loader state followed by the ELF.
+    fn deploy_loader_v4_program(&mut self, program: &TridentProgram) {
+        let (authority, status) = match program.authority {
+            Some(authority) => (authority, LoaderV4Status::Deployed),
+            None => (Pubkey::default(), LoaderV4Status::Finalized),
+        };
+
+        // `LoaderV4State` is stored in its in-memory `repr(C)` layout
+        let mut data =
+            Vec::with_capacity(LoaderV4State::program_data_offset() + program.data.len());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(&(status as u64).to_le_bytes());
+        data.extend_from_slice(&program.data);
+
+        let account_data = AccountSharedData::create(
+            Rent::default().minimum_balance(data.len()),
+            data,
+            solana_sdk_ids::loader_v4::id(),
+            true,
+            Default::default(),
+        );
+
+        self.accounts
+            .set_program(&program.program_id, &account_data);
+    }
+
+    /// Replaces the ELF of a deployed upgradeable program, the same way the
+    /// `Upgrade` instruction of the upgradeable loader does.
+    ///
+    /// The ProgramData account is rewritten with the current slot and keeps its upgrade
+    /// authority, which has to match `authority`. Programs without an authority cannot
+    /// be upgraded. If the new ELF needs a larger rent exempt balance, the difference is
+    /// paid by the payer. The new ELF is visible to the next processed transaction.
+    pub fn upgrade_program(
+        &mut self,
+        program_id: &Pubkey,
+        authority: &Pubkey,
+        program_data: &[u8],
+    ) -> Result<(), InstructionError> {
+        let (programdata_address, upgrade_authority_address) =
+            self.get_upgradeable_program(program_id)?;
+        check_upgrade_authority(upgrade_authority_address, authority)?;
+
+        // the loader refuses to deploy ELFs which do not pass verification
+        let environment = self
+            .processor
+            .program_cache
+            .read()
+            .expect("Failed to read program cache")
+            .environments
+            .program_runtime_v1
+            .clone();
+        let executable = Executable::<InvokeContext<'static>>::load(program_data, environment)
+            .map_err(|_| InstructionError::InvalidAccountData)?;
+        executable
+            .verify::<RequisiteVerifier>()
+            .map_err(|_| InstructionError::InvalidAccountData)?;
+
+        let slot = self.accounts.get_sysvar::<Clock>().slot;
+        let state = UpgradeableLoaderState::ProgramData {
+            slot,
+            upgrade_authority_address: Some(*authority),
+        };
+        let mut data =
+            bincode::serialize(&state).map_err(|_| InstructionError::InvalidAccountData)?;
+        data.resize(UpgradeableLoaderState::size_of_programdata_metadata(), 0);
+        data.extend_from_slice(program_data);
+
+        let balance = self
+            .accounts
+            .get_program(&programdata_address)
+            .map_or(0, |account| account.lamports());
+        let shortfall = self
+            .get_sysvar::<Rent>()
+            .minimum_balance(data.len())
+            .saturating_sub(balance);
+        self.debit_payer(shortfall)?;
+
+        let account_data = AccountSharedData::create(
+            balance + shortfall,
+            data,
+            solana_sdk_ids::bpf_loader_upgradeable::id(),
+            true,
+            Default::default(),
+        );
+        self.accounts
+            .set_program(&programdata_address, &account_data);
+
+        self.invalidate_program(program_id);
+        Ok(())
+    }
+
+    /// Closes a deployed upgradeable program, the same way the `Close` instruction
+    /// of the upgradeable loader does. The lamports of the ProgramData account are
+    /// transferred to `recipient` and the program can no longer be invoked.
+    ///
+    /// The upgrade authority has to match `authority`, programs without an upgrade
+    /// authority cannot be closed.
+    pub fn close_program(
+        &mut self,
+        program_id: &Pubkey,
+        authority: &Pubkey,
+        recipient: &Pubkey,
+    ) -> Result<(), InstructionError> {
+        let (programdata_address, upgrade_authority_address) =
+            self.get_upgradeable_program(program_id)?;
+        check_upgrade_authority(upgrade_authority_address, authority)?;
+
+        let programdata_account = self
+            .accounts
+            .get_program(&programdata_address)
+            .ok_or(InstructionError::InvalidAccountData)?;
+
+        let mut recipient_account = self
+            .get_account(recipient)
+            .unwrap_or_else(|| AccountSharedData::new(0, 0, &solana_sdk_ids::system_program::id()));
+        recipient_account.checked_add_lamports(programdata_account.lamports())?;
+        let permanent = self.accounts.get_permanent_account(recipient).is_some();
+        self.set_account(recipient, &recipient_account, permanent);
+
+        let data = bincode::serialize(&UpgradeableLoaderState::Uninitialized)
+            .map_err(|_| InstructionError::InvalidAccountData)?;
+        let closed_account = AccountSharedData::create(
+            0,
+            data,
+            solana_sdk_ids::bpf_loader_upgradeable::id(),
+            false,
+            Default::default(),
+        );
+        self.accounts
+            .set_program(&programdata_address, &closed_account);
+
+        self.invalidate_program(program_id);
+        Ok(())
+    }
+
+    /// Withdraws lamports from the payer, e.g. to keep a resized account rent exempt.
+    fn debit_payer(&mut self, lamports: u64) -> Result<(), InstructionError> {
+        if lamports == 0 {
+            return Ok(());
+        }
+        let payer = self.payer.pubkey();
+        let mut payer_account = self
+            .get_account(&payer)
+            .ok_or(InstructionError::InsufficientFunds)?;
+        payer_account.checked_sub_lamports(lamports)?;
+        let permanent = self.accounts.get_permanent_account(&payer).is_some();
+        self.set_account(&payer, &payer_account, permanent);
+        Ok(())
+    }
+
+    /// Returns the ProgramData address and upgrade authority of an upgradeable program.
+    fn get_upgradeable_program(
+        &self,
+        program_id: &Pubkey,
+    ) -> Result<(Pubkey, Option<Pubkey>), InstructionError> {
+        let program = self
+            .accounts
+            .get_program(program_id)
+            .ok_or(InstructionError::IncorrectProgramId)?;
+        if !solana_sdk_ids::bpf_loader_upgradeable::check_id(program.owner()) {
+            return Err(InstructionError::IncorrectProgramId);
+        }
+        let Ok(UpgradeableLoaderState::Program {
+            programdata_address,
+        }) = bincode::deserialize(program.data())
+        else {
+            return Err(InstructionError::InvalidAccountData);
+        };
+
+        let programdata = self
+            .accounts
+            .get_program(&programdata_address)
+            .ok_or(InstructionError::InvalidAccountData)?;
+        match bincode::deserialize(programdata.data()) {
+            Ok(UpgradeableLoaderState::ProgramData {
+                upgrade_authority_address,
+                ..
+            }) => Ok((programdata_address, upgrade_authority_address)),
+            _ => Err(InstructionError::InvalidAccountData),
+        }
+    }
+
+    /// Drops the compiled program and replaces it with the one compiled from
+    /// its current ELF, see [`compiled_program`].
+    pub(crate) fn invalidate_program(&mut self, program_id: &Pubkey) {
+        let compiled_program = self.get_compiled_program(program_id);
+        let mut cache = self
+            .processor
+            .program_cache
+            .write()
+            .expect("Failed to write to program cache");
+        cache.remove_programs(std::iter::once(*program_id));
+
+        if let Some(entry) = compiled_program {
+            // the entry is deployed at slot 0, so it is visible at every slot
+            cache.assign_program(*program_id, entry);
+        }
+        drop(cache);
+
+        if let Some(profiler) = self.cu_profiler.as_mut() {
+            profiler.invalidate(program_id);
+        }
+    }
+
+    /// Programs deployed at `slot` become visible one slot later, moves the
+    /// processor past that slot if necessary.
+    pub(crate) fn advance_program_slot(&mut self, slot: u64) {
+        let visible_slot = slot.saturating_add(1);
+        let mut cache = self
+            .processor
+            .program_cache
+            .write()
+            .expect("Failed to write to program cache");
+        // The fork graph does not relate any slots, the processor slot is kept
+        // equal to the root slot of the cache so that all deployed programs are found.
+        if cache.latest_root_slot >= visible_slot {
+            return;
+        }
+        cache.latest_root_slot = visible_slot;
+        drop(cache);
+
+        let epoch = self.accounts.get_sysvar::<Clock>().epoch;
+        self.processor = self.processor.new_from(visible_slot, epoch);
+    }
+
+    /// Deploys the program executed natively by its entrypoint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entrypoint is a native `process_instruction` function and the syscall
+    /// stubs are not installed through `with_syscalls_v2` of the builder.
     #[cfg(feature = "syscall-v2")]
     pub fn deploy_entrypoint_program(&mut self, program: &TridentEntrypoint) {
         use crate::utils::create_loadable_account_for_test;
 
         let entry = match program.entry {
-            Some(entry) => entry,
+            Some(ProgramEntry::Builtin(entry)) => {
+                self.native_programs.remove(&program.program_id);
+                entry
+            }
+            Some(ProgramEntry::Native(entry)) => {
+                // native programs set return data and invoke other programs through the syscall stubs
+                assert!(
+                    self.syscalls_v2,
+                    "Native programs require the syscall stubs, see TridentSVMBuilder::with_syscalls_v2"
+                );
+                self.native_programs.insert(program.program_id, entry);
+                native_entrypoint
+            }
             None => panic!("Native programs have to have entry specified"),
         };
 
@@ -113,11 +403,256 @@ impl TridentSVM {
         self.accounts
             .set_program(&program_data_account, &account_data);
 
-        self.processor.add_builtin(
-            self,
-            program.program_id,
-            "program-name",
-            ProgramCacheEntry::new_builtin(0, "program-name".len(), entry),
+        // native programs are profiled by wrapping their entrypoint
+        let program_entry = if self.cu_profiler.is_some() {
+            instrumented_native_program("program-name", entry)
+        } else {
+            ProgramCacheEntry::new_builtin(0, "program-name".len(), entry)
+        };
+
+        self.add_builtin(program.program_id, program_entry);
+    }
+
+    /// Returns the compiled program from the process wide cache, compiling it if necessary.
+    fn get_compiled_program(&self, program_id: &Pubkey) -> Option<Arc<ProgramCacheEntry>> {
+        let program = self.accounts.get_program(program_id)?;
+        let elf = self.get_program_elf(program_id)?;
+        let environment = Arc::clone(
+            &self
+                .processor
+                .program_cache
+                .read()
+                .expect("Failed to read program cache")
+                .environments
+                .program_runtime_v1,
+        );
+        let account_size = if solana_sdk_ids::bpf_loader_upgradeable::check_id(program.owner()) {
+            program.data().len()
+                + UpgradeableLoaderState::size_of_programdata_metadata()
+                + elf.len()
+        } else {
+            program.data().len()
+        };
+        compiled_program(
+            self.environment_key(),
+            &environment,
+            program.owner(),
+            &elf,
+            account_size,
+        )
+    }
+
+    /// Returns the ELF of a deployed SBF program.
+    pub(crate) fn get_program_elf(&self, program_id: &Pubkey) -> Option<Vec<u8>> {
+        let program = self.accounts.get_program(program_id)?;
+
+        if solana_sdk_ids::bpf_loader_upgradeable::check_id(program.owner()) {
+            let UpgradeableLoaderState::Program {
+                programdata_address,
+            } = bincode::deserialize(program.data()).ok()?
+            else {
+                return None;
+            };
+            let program_data = self.accounts.get_program(&programdata_address)?;
+            program_data
+                .data()
+                .get(UpgradeableLoaderState::size_of_programdata_metadata()..)
+                .map(|elf| elf.to_vec())
+        } else if solana_sdk_ids::bpf_loader::check_id(program.owner())
+            || solana_sdk_ids::bpf_loader_deprecated::check_id(program.owner())
+        {
+            Some(program.data().to_vec())
+        } else if solana_sdk_ids::loader_v4::check_id(program.owner()) {
+            program
+                .data()
+                .get(LoaderV4State::program_data_offset()..)
+                .map(|elf| elf.to_vec())
+        } else {
+            None
+        }
+    }
+}
+
+/// Checks the authority the same way the `Upgrade` and `Close` instructions do.
+fn check_upgrade_authority(
+    upgrade_authority_address: Option<Pubkey>,
+    authority: &Pubkey,
+) -> Result<(), InstructionError> {
+    match upgrade_authority_address {
+        None => Err(InstructionError::Immutable),
+        Some(upgrade_authority) if upgrade_authority != *authority => {
+            Err(InstructionError::IncorrectAuthority)
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_account::AccountSharedData;
+    use solana_account::ReadableAccount;
+    use solana_clock::Clock;
+    use solana_hash::Hash;
+    use solana_instruction::error::InstructionError;
+    use solana_instruction::AccountMeta;
+    use solana_instruction::Instruction;
+    use solana_keypair::Keypair;
+    use solana_loader_v3_interface::state::UpgradeableLoaderState;
+    use solana_pubkey::Pubkey;
+    use solana_rent::Rent;
+    use solana_signer::Signer;
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+    use solana_transaction::Transaction;
+
+    use crate::trident_svm::TridentSVM;
+    use crate::types::trident_program::ProgramLoader;
+    use crate::types::trident_program::TridentProgram;
+
+    /// Mint with space for the MintCloseAuthority extension of Token 2022.
+    const MINT_WITH_CLOSE_AUTHORITY_SIZE: usize = 234;
+
+    fn initialize_mint_close_authority(svm: &mut TridentSVM, program_id: &Pubkey) -> bool {
+        let payer = svm.get_payer();
+        let mint = Keypair::new();
+        let mint_account = AccountSharedData::new(
+            Rent::default().minimum_balance(MINT_WITH_CLOSE_AUTHORITY_SIZE),
+            MINT_WITH_CLOSE_AUTHORITY_SIZE,
+            program_id,
+        );
+        svm.set_account(&mint.pubkey(), &mint_account, false);
+
+        // InitializeMintCloseAuthority without a close authority, only supported by Token 2022
+        let instruction = Instruction::new_with_bytes(
+            *program_id,
+            &[25, 0],
+            vec![AccountMeta::new(mint.pubkey(), false)],
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        let result = svm.process_transaction_with_settle(transaction);
+        result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok()
+    }
+
+    #[test]
+    fn test_upgrade_and_close_program() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut svm = TridentSVM::default();
+        svm.deploy_binary_program(&TridentProgram::new(
+            program_id,
+            Some(authority),
+            include_bytes!("../solana-program-library/spl-token-mainnet.so").to_vec(),
+        ));
+        assert!(!initialize_mint_close_authority(&mut svm, &program_id));
+
+        svm.set_sysvar(&Clock {
+            slot: 10,
+            ..svm.get_sysvar::<Clock>()
+        });
+        let token_2022 = include_bytes!("../solana-program-library/spl-2022-token-mainnet.so");
+        assert_eq!(
+            svm.upgrade_program(&program_id, &Pubkey::new_unique(), token_2022),
+            Err(InstructionError::IncorrectAuthority)
+        );
+        let payer_balance = svm
+            .get_account(&svm.get_payer().pubkey())
+            .unwrap()
+            .lamports();
+        svm.upgrade_program(&program_id, &authority, token_2022)
+            .unwrap();
+        assert!(initialize_mint_close_authority(&mut svm, &program_id));
+
+        let programdata_address = solana_loader_v3_interface::get_program_data_address(&program_id);
+        let programdata = svm.get_account(&programdata_address).unwrap();
+        assert_eq!(
+            bincode::deserialize::<UpgradeableLoaderState>(programdata.data()).unwrap(),
+            UpgradeableLoaderState::ProgramData {
+                slot: 10,
+                upgrade_authority_address: Some(authority),
+            }
+        );
+        // the larger ELF is funded by the payer
+        assert_eq!(
+            programdata.lamports(),
+            Rent::default().minimum_balance(programdata.data().len())
+        );
+        assert!(
+            svm.get_account(&svm.get_payer().pubkey())
+                .unwrap()
+                .lamports()
+                < payer_balance
+        );
+        assert_eq!(
+            svm.upgrade_program(&program_id, &authority, b"not an elf"),
+            Err(InstructionError::InvalidAccountData)
+        );
+
+        let recipient = Pubkey::new_unique();
+        assert_eq!(
+            svm.close_program(&program_id, &Pubkey::new_unique(), &recipient),
+            Err(InstructionError::IncorrectAuthority)
+        );
+        svm.close_program(&program_id, &authority, &recipient)
+            .unwrap();
+        assert_eq!(
+            svm.get_account(&recipient).unwrap().lamports(),
+            programdata.lamports()
+        );
+        assert!(!initialize_mint_close_authority(&mut svm, &program_id));
+    }
+
+    #[test]
+    fn test_deploy_with_loader_selector() {
+        // Token 2022 is built for the aligned input layout, which the deprecated loader does not use
+        for (loader, authority, succeeds) in [
+            (ProgramLoader::NonUpgradeable, None, true),
+            (ProgramLoader::Deprecated, None, false),
+            (ProgramLoader::V4, Some(Pubkey::new_unique()), true),
+            (ProgramLoader::V4, None, true),
+        ] {
+            let program_id = Pubkey::new_unique();
+            let mut svm = TridentSVM::default();
+            svm.deploy_binary_program(
+                &TridentProgram::new(
+                    program_id,
+                    authority,
+                    include_bytes!("../solana-program-library/spl-2022-token-mainnet.so").to_vec(),
+                )
+                .with_loader(loader),
+            );
+
+            assert_eq!(svm.get_account(&program_id).unwrap().owner(), &loader.id());
+            assert_eq!(
+                initialize_mint_close_authority(&mut svm, &program_id),
+                succeeds
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "spl-token")]
+    fn test_immutable_program_cannot_be_upgraded() {
+        let token_program = solana_pubkey::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+        let authority = Pubkey::new_unique();
+        let mut svm = TridentSVM::default();
+
+        assert_eq!(
+            svm.upgrade_program(&token_program, &authority, &[]),
+            Err(InstructionError::Immutable)
+        );
+        assert_eq!(
+            svm.close_program(&token_program, &authority, &Pubkey::new_unique()),
+            Err(InstructionError::Immutable)
+        );
+        assert_eq!(
+            svm.upgrade_program(&Pubkey::new_unique(), &authority, &[]),
+            Err(InstructionError::IncorrectProgramId)
         );
     }
 }
@@ -0,0 +1,323 @@
+use solana_account::ReadableAccount;
+use solana_clock::Clock;
+use solana_hash::Hash;
+use solana_sha256_hasher::Hasher;
+use solana_svm::transaction_processing_result::ProcessedTransaction;
+use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+use solana_svm::transaction_processor::LoadAndExecuteSanitizedTransactionsOutput;
+use solana_transaction::Transaction;
+
+use crate::trident_svm::TridentSVM;
+use crate::types::transaction_recording::RecordedTransaction;
+use crate::types::transaction_recording::ReplayMismatch;
+use crate::types::transaction_recording::TransactionRecording;
+
+impl TridentSVM {
+    /// Records every processed transaction together with its clock, injected faults, result
+    /// and the resulting writable accounts, an already started recording is kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a program is mocked, mocks cannot be recorded and replayed.
+    pub fn start_transaction_recording(&mut self) {
+        assert!(
+            self.mocks.is_empty(),
+            "Transactions cannot be recorded while programs are mocked"
+        );
+        self.transaction_recording.get_or_insert_default();
+    }
+
+    /// Stops the recording and returns the recorded transactions.
+    pub fn stop_transaction_recording(&mut self) -> Option<TransactionRecording> {
+        self.transaction_recording.take()
+    }
+
+    /// Transactions recorded so far, `None` if transactions are not recorded.
+    pub fn get_transaction_recording(&self) -> Option<&TransactionRecording> {
+        self.transaction_recording.as_ref()
+    }
+
+    /// Returns the recorded transactions and starts recording from scratch.
+    pub fn take_transaction_recording(&mut self) -> Option<TransactionRecording> {
+        self.transaction_recording.as_mut().map(std::mem::take)
+    }
+
+    /// Processes the recorded transactions in order, each at its recorded clock and with its
+    /// recorded faults and execution mode, and checks that their results and writable accounts
+    /// match the recorded ones.
+    ///
+    /// The instance should start from the state the recording started from, such as an
+    /// instance built with the same seed, programs and accounts. Replaying stops at the first
+    /// transaction with a different outcome, as the following ones see a different state.
+    /// The fault injection and the execution mode of the instance are restored afterwards.
+    pub fn replay_transactions(
+        &mut self,
+        recording: &TransactionRecording,
+    ) -> Result<(), ReplayMismatch> {
+        // the recorded clocks are used as they are, without following the system time
+        let deterministic = std::mem::replace(
+            &mut self
+                .accounts
+                .sysvar_tracker
+                .get_mut()
+                .expect("Sysvar tracker lock poisoned")
+                .deterministic,
+            true,
+        );
+
+        let fault_injection = self.fault_injection.clone();
+        let execution_mode = self.execution_mode;
+
+        let mut mismatch = None;
+        for (index, recorded) in recording.transactions().iter().enumerate() {
+            self.set_sysvar(&recorded.clock);
+            self.set_fault_injection(recorded.fault_injection.clone());
+            self.set_execution_mode(recorded.execution_mode);
+            let transaction = recorded.transaction.clone();
+            let result = match recorded.settled {
+                true => self.process_transaction_with_settle(transaction),
+                false => self.process_transaction(transaction),
+            };
+            let output = result.get_result();
+            let replayed = output.processing_results[0].flattened_result();
+            let accounts_match =
+                accounts_hash(&recorded.transaction, output) == recorded.accounts_hash;
+            if replayed != recorded.result || !accounts_match {
+                mismatch = Some(ReplayMismatch {
+                    index,
+                    recorded: recorded.result.clone(),
+                    replayed,
+                    accounts_match,
+                });
+                break;
+            }
+        }
+
+        self.set_fault_injection(fault_injection);
+        self.set_execution_mode(execution_mode);
+
+        self.accounts
+            .sysvar_tracker
+            .get_mut()
+            .expect("Sysvar tracker lock poisoned")
+            .deterministic = deterministic;
+        match mismatch {
+            Some(mismatch) => Err(mismatch),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn record_transaction(
+        &mut self,
+        transaction: Transaction,
+        clock: Clock,
+        settled: bool,
+        output: &LoadAndExecuteSanitizedTransactionsOutput,
+    ) {
+        if let Some(recording) = self.transaction_recording.as_mut() {
+            recording.record(RecordedTransaction {
+                accounts_hash: accounts_hash(&transaction, output),
+                transaction,
+                clock,
+                settled,
+                fault_injection: self.fault_injection.clone(),
+                execution_mode: self.execution_mode,
+                result: output.processing_results[0].flattened_result(),
+            });
+        }
+    }
+}
+
+/// Hashes the writable accounts of the executed transaction as they are after the execution.
+fn accounts_hash(
+    transaction: &Transaction,
+    output: &LoadAndExecuteSanitizedTransactionsOutput,
+) -> Option<Hash> {
+    let Ok(ProcessedTransaction::Executed(executed)) = &output.processing_results[0] else {
+        return None;
+    };
+    let mut hasher = Hasher::default();
+    for (index, (address, account)) in executed.loaded_transaction.accounts.iter().enumerate() {
+        if !transaction.message.is_maybe_writable(index, None) {
+            continue;
+        }
+        hasher.hashv(&[
+            address.as_ref(),
+            &account.lamports().to_le_bytes(),
+            account.owner().as_ref(),
+            &[account.executable() as u8],
+            account.data(),
+        ]);
+    }
+    Some(hasher.result())
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_account::ReadableAccount;
+    use solana_clock::Clock;
+    use solana_hash::Hash;
+    use solana_instruction::error::InstructionError;
+    use solana_instruction::AccountMeta;
+    use solana_instruction::Instruction;
+    use solana_keypair::Keypair;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+    use solana_transaction::Transaction;
+    use solana_transaction_error::TransactionError;
+
+    use crate::trident_svm::TridentSVM;
+    use crate::types::fault_injection::FaultInjection;
+    use crate::types::transaction_recording::TransactionRecording;
+
+    fn build() -> TridentSVM {
+        let mut builder = TridentSVM::builder();
+        builder.without_bundled_programs();
+        builder.with_seed(7);
+        builder.with_transaction_recording();
+        builder.build()
+    }
+
+    fn transfer(
+        svm: &TridentSVM,
+        sender: &Keypair,
+        recipient: &Pubkey,
+        lamports: u64,
+    ) -> Transaction {
+        let payer = svm.get_payer();
+        // SystemInstruction::Transfer
+        let mut data = vec![2, 0, 0, 0];
+        data.extend_from_slice(&lamports.to_le_bytes());
+        let instruction = Instruction::new_with_bytes(
+            solana_sdk_ids::system_program::id(),
+            &data,
+            vec![
+                AccountMeta::new(sender.pubkey(), true),
+                AccountMeta::new(*recipient, false),
+            ],
+        );
+        Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer, sender],
+            Hash::default(),
+        )
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut svm = build();
+        let sender = svm.create_funded_keypair(10_000_000);
+        let recipient = Pubkey::new_unique();
+
+        svm.process_transaction_with_settle(transfer(&svm, &sender, &recipient, 4_000_000));
+        svm.process_transaction_with_settle(transfer(&svm, &sender, &recipient, 10_000_000));
+        let clock = svm.get_sysvar::<Clock>();
+        svm.set_sysvar(&Clock {
+            unix_timestamp: clock.unix_timestamp + 600,
+            ..clock
+        });
+        svm.process_transaction(transfer(&svm, &sender, &recipient, 1_000_000));
+
+        let recording = svm.take_transaction_recording().unwrap();
+        let results: Vec<_> = recording
+            .transactions()
+            .iter()
+            .map(|recorded| recorded.result.clone())
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                Ok(()),
+                // SystemError::ResultWithNegativeLamports
+                Err(TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(1)
+                )),
+                Ok(()),
+            ]
+        );
+        assert_eq!(
+            recording.transactions()[2].clock.unix_timestamp,
+            clock.unix_timestamp + 600
+        );
+
+        let path = std::env::temp_dir().join(format!("trident-recording-{}", Pubkey::new_unique()));
+        recording.save(&path).unwrap();
+        let loaded = TransactionRecording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, recording);
+
+        let mut replaying = build();
+        assert_eq!(
+            replaying.create_funded_keypair(10_000_000).pubkey(),
+            sender.pubkey()
+        );
+        assert_eq!(replaying.replay_transactions(&loaded), Ok(()));
+        assert_eq!(replaying.get_transaction_recording(), Some(&recording));
+        assert_eq!(
+            replaying.get_account(&recipient).unwrap().lamports(),
+            4_000_000
+        );
+
+        // the sender is not funded
+        let mut diverging = build();
+        let mismatch = diverging.replay_transactions(&loaded).unwrap_err();
+        assert_eq!(mismatch.index, 0);
+        assert_eq!(mismatch.recorded, Ok(()));
+        assert_eq!(
+            mismatch.replayed,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_replay_compares_accounts() {
+        let mut svm = build();
+        let sender = svm.create_funded_keypair(10_000_000);
+        let recipient = Pubkey::new_unique();
+        svm.process_transaction_with_settle(transfer(&svm, &sender, &recipient, 4_000_000));
+        let recording = svm.take_transaction_recording().unwrap();
+        assert!(recording.transactions()[0].accounts_hash.is_some());
+
+        // the transfer succeeds, but the recipient ends with a different balance
+        let mut diverging = build();
+        diverging.create_funded_keypair(10_000_000);
+        diverging.airdrop(&recipient, 1_000_000);
+        let mismatch = diverging.replay_transactions(&recording).unwrap_err();
+        assert_eq!(mismatch.index, 0);
+        assert_eq!(mismatch.recorded, Ok(()));
+        assert_eq!(mismatch.replayed, Ok(()));
+        assert!(!mismatch.accounts_match);
+    }
+
+    #[test]
+    fn test_replay_injects_recorded_faults() {
+        let mut svm = build();
+        let sender = svm.create_funded_keypair(10_000_000);
+        let recipient = Pubkey::new_unique();
+        svm.set_fault_injection(FaultInjection::new().with_missing_account(sender.pubkey()));
+        svm.process_transaction_with_settle(transfer(&svm, &sender, &recipient, 4_000_000));
+        let recording = svm.take_transaction_recording().unwrap();
+        assert_eq!(
+            recording.transactions()[0].fault_injection,
+            FaultInjection::new().with_missing_account(sender.pubkey())
+        );
+
+        let mut replaying = build();
+        replaying.create_funded_keypair(10_000_000);
+        assert_eq!(replaying.replay_transactions(&recording), Ok(()));
+        assert_eq!(replaying.get_fault_injection(), &FaultInjection::default());
+        assert!(replaying.get_account(&recipient).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Programs cannot be mocked while transactions are recorded")]
+    fn test_mocks_are_not_recorded() {
+        let mut svm = build();
+        svm.mock_program(&Pubkey::new_unique(), |_| Ok(()));
+    }
+}
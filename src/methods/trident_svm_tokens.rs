@@ -0,0 +1,240 @@
+use solana_account::ReadableAccount;
+use solana_pubkey::Pubkey;
+
+use crate::trident_svm::TridentSVM;
+use crate::types::bundled_program::BundledProgram;
+use crate::types::token_state::AccountExtension;
+use crate::types::token_state::TokenAccount;
+use crate::types::token_state::TokenMint;
+
+impl TridentSVM {
+    /// Stores the mint owned by the token program, either SPL Token or Token 2022.
+    pub fn set_token_mint(&mut self, address: &Pubkey, mint: &TokenMint, token_program: &Pubkey) {
+        self.set_rent_exempt_account(address, &mint.serialize(), token_program, false);
+    }
+
+    /// Stores the token account owned by the token program, either SPL Token or Token 2022.
+    pub fn set_token_account(
+        &mut self,
+        address: &Pubkey,
+        token_account: &TokenAccount,
+        token_program: &Pubkey,
+    ) {
+        self.set_rent_exempt_account(address, &token_account.serialize(), token_program, false);
+    }
+
+    pub fn get_token_mint(&self, address: &Pubkey) -> Option<TokenMint> {
+        let account = self.get_account(address)?;
+        is_token_program(account.owner())
+            .then(|| TokenMint::deserialize(account.data()))
+            .flatten()
+    }
+
+    pub fn get_token_account(&self, address: &Pubkey) -> Option<TokenAccount> {
+        let account = self.get_account(address)?;
+        is_token_program(account.owner())
+            .then(|| TokenAccount::deserialize(account.data()))
+            .flatten()
+    }
+
+    pub fn get_token_balance(&self, address: &Pubkey) -> Option<u64> {
+        self.get_token_account(address)
+            .map(|token_account| token_account.amount)
+    }
+
+    /// Creates a mint without supply, extensions and freeze authority.
+    pub fn create_mint(
+        &mut self,
+        address: &Pubkey,
+        mint_authority: &Pubkey,
+        decimals: u8,
+        token_program: &Pubkey,
+    ) {
+        self.set_token_mint(
+            address,
+            &TokenMint {
+                mint_authority: Some(*mint_authority),
+                decimals,
+                ..Default::default()
+            },
+            token_program,
+        );
+    }
+
+    /// Creates a token account holding `amount` tokens, which are added to the supply of the mint.
+    ///
+    /// Account extensions required by the extensions of the mint are added as Token 2022 would.
+    /// Panics if the mint does not exist.
+    pub fn create_token_account(
+        &mut self,
+        address: &Pubkey,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+    ) {
+        self.create_token_account_with_extensions(address, mint, owner, amount, vec![]);
+    }
+
+    /// Creates the associated token account of the owner, see [`TridentSVM::create_token_account`].
+    pub fn create_associated_token_account(
+        &mut self,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let token_program = *self.get_account(mint).expect("Mint does not exist").owner();
+        let address = TokenAccount::associated_address(owner, mint, &token_program);
+        // the associated token program initializes Token 2022 accounts as immutable
+        let extensions = if token_program == BundledProgram::SplToken2022.program_id() {
+            vec![AccountExtension::ImmutableOwner]
+        } else {
+            vec![]
+        };
+        self.create_token_account_with_extensions(&address, mint, owner, amount, extensions);
+        address
+    }
+
+    fn create_token_account_with_extensions(
+        &mut self,
+        address: &Pubkey,
+        mint_address: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+        mut extensions: Vec<AccountExtension>,
+    ) {
+        let token_program = *self
+            .get_account(mint_address)
+            .expect("Mint does not exist")
+            .owner();
+        let mut mint = self
+            .get_token_mint(mint_address)
+            .expect("Account is not a mint");
+        extensions.extend(
+            mint.extensions
+                .iter()
+                .filter_map(|extension| extension.required_account_extension()),
+        );
+
+        self.set_token_account(
+            address,
+            &TokenAccount {
+                mint: *mint_address,
+                owner: *owner,
+                amount,
+                extensions,
+                ..Default::default()
+            },
+            &token_program,
+        );
+
+        mint.supply = mint.supply.saturating_add(amount);
+        self.set_token_mint(mint_address, &mint, &token_program);
+    }
+}
+
+fn is_token_program(program_id: &Pubkey) -> bool {
+    program_id == &BundledProgram::SplToken.program_id()
+        || program_id == &BundledProgram::SplToken2022.program_id()
+}
+
+#[cfg(all(test, feature = "spl-token-2022"))]
+mod tests {
+    use solana_hash::Hash;
+    use solana_instruction::AccountMeta;
+    use solana_instruction::Instruction;
+    use solana_keypair::Keypair;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+    use solana_transaction::Transaction;
+
+    use crate::trident_svm::TridentSVM;
+    use crate::types::bundled_program::BundledProgram;
+    use crate::types::token_state::AccountExtension;
+    use crate::types::token_state::MintExtension;
+    use crate::types::token_state::TokenMint;
+
+    #[test]
+    fn test_token_2022_transfer_with_fee() {
+        let mut svm = TridentSVM::default();
+        let token_program = BundledProgram::SplToken2022.program_id();
+        let payer = svm.get_payer();
+        let sender = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+
+        let extensions = vec![
+            MintExtension::TransferFeeConfig {
+                transfer_fee_config_authority: Some(payer.pubkey()),
+                withdraw_withheld_authority: Some(payer.pubkey()),
+                withheld_amount: 0,
+                transfer_fee_basis_points: 100,
+                maximum_fee: 1_000,
+            },
+            MintExtension::InterestBearingConfig {
+                rate_authority: None,
+                initialization_timestamp: 0,
+                pre_update_average_rate: 500,
+                last_update_timestamp: 0,
+                current_rate: 500,
+            },
+            MintExtension::ConfidentialTransferMint {
+                authority: Some(payer.pubkey()),
+                auto_approve_new_accounts: true,
+                auditor_elgamal_pubkey: None,
+            },
+        ];
+        svm.set_token_mint(
+            &mint,
+            &TokenMint {
+                mint_authority: Some(payer.pubkey()),
+                decimals: 6,
+                extensions: extensions.clone(),
+                ..Default::default()
+            },
+            &token_program,
+        );
+        let source = svm.create_associated_token_account(&sender.pubkey(), &mint, 50_000);
+        let destination = svm.create_associated_token_account(&recipient, &mint, 0);
+        assert_eq!(svm.get_token_mint(&mint).unwrap().supply, 50_000);
+        assert_eq!(svm.get_token_mint(&mint).unwrap().extensions, extensions);
+
+        // TransferChecked of 10 000 tokens with 6 decimals
+        let mut data = vec![12];
+        data.extend_from_slice(&10_000u64.to_le_bytes());
+        data.push(6);
+        let instruction = Instruction::new_with_bytes(
+            token_program,
+            &data,
+            vec![
+                AccountMeta::new(source, false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(destination, false),
+                AccountMeta::new_readonly(sender.pubkey(), true),
+            ],
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer, &sender],
+            Hash::default(),
+        );
+        let result = svm.process_transaction_with_settle(transaction);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+
+        assert_eq!(svm.get_token_balance(&source), Some(40_000));
+        let destination = svm.get_token_account(&destination).unwrap();
+        assert_eq!(destination.amount, 9_900);
+        assert_eq!(
+            destination.extensions,
+            [
+                AccountExtension::ImmutableOwner,
+                AccountExtension::TransferFeeAmount {
+                    withheld_amount: 100
+                }
+            ]
+        );
+    }
+}
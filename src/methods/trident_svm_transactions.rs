@@ -1,19 +1,31 @@
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::sync::Arc;
 
 use solana_clock::Clock;
 use solana_compute_budget::compute_budget_limits::ComputeBudgetLimits;
 use solana_fee_structure::FeeDetails;
+use solana_program_runtime::loaded_programs::ProgramCacheEntryType;
+use solana_pubkey::Pubkey;
 
 use solana_transaction::sanitized::SanitizedTransaction;
 use solana_transaction::Transaction;
 
 use solana_svm::account_loader::CheckedTransactionDetails;
+use solana_svm::transaction_processing_result::ProcessedTransaction;
 use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
 use solana_svm::transaction_processor::ExecutionRecordingConfig;
 use solana_svm::transaction_processor::TransactionProcessingConfig;
 use solana_svm::transaction_processor::TransactionProcessingEnvironment;
 
+#[cfg(feature = "syscall-v2")]
+use crate::builtin_function::ActiveNativePrograms;
+use crate::fault_injection::ActiveSyscallFaults;
+use crate::instrumentation::take_execution_record;
+use crate::mocking::ActiveMocks;
 use crate::trident_svm::TridentSVM;
+use crate::types::execution_mode::ExecutionMode;
+use crate::types::fault_injection::FaultInjection;
 use crate::types::transaction_result::TridentTransactionProcessingResult;
 
 impl TridentSVM {
@@ -21,57 +33,33 @@ impl TridentSVM {
         &mut self,
         transaction: Transaction,
     ) -> TridentTransactionProcessingResult {
-        let tx_processing_environment = TransactionProcessingEnvironment::<'_> {
-            feature_set: *self.feature_set,
-            ..Default::default()
-        };
-
-        let tx_processing_config = TransactionProcessingConfig::default();
-
-        // reset sysvar cache
-        self.processor.reset_sysvar_cache();
-
-        // replenish sysvar cache with sysvars from the accounts db
-        self.processor.fill_missing_sysvar_cache_entries(self);
-
-        // create sanitized transaction
-        let sanitezed_tx =
-            SanitizedTransaction::try_from_legacy_transaction(transaction, &HashSet::new())
-                .unwrap();
-
-        // get current transaction timestamp
-        let transaction_timestamp =
-            self.accounts.deserialize_sysvar::<Clock>().unix_timestamp as u64;
-
-        // execute transaction
-        let res = self.processor.load_and_execute_sanitized_transactions(
-            self,
-            &[sanitezed_tx],
-            get_transaction_check_results(1),
-            &tx_processing_environment,
-            &tx_processing_config,
-        );
-
-        // update clock
-        self.accounts.update_clock();
-
-        // return transaction processing result
-        TridentTransactionProcessingResult::new(res, transaction_timestamp)
+        self.execute(transaction, TransactionProcessingConfig::default(), false)
     }
+
     pub fn process_transaction_with_settle(
         &mut self,
         transaction: Transaction,
     ) -> TridentTransactionProcessingResult {
-        let tx_processing_environment = TransactionProcessingEnvironment::<'_> {
-            feature_set: *self.feature_set,
-            ..Default::default()
-        };
-
         let tx_processing_config = TransactionProcessingConfig {
             log_messages_bytes_limit: Some(20 * 1000),
             recording_config: ExecutionRecordingConfig::new_single_setting(true),
             ..Default::default()
         };
+        self.execute(transaction, tx_processing_config, true)
+    }
+
+    /// Executes the transaction, the changed accounts are stored only if `settle` is set
+    /// and the transaction succeeds.
+    fn execute(
+        &mut self,
+        transaction: Transaction,
+        tx_processing_config: TransactionProcessingConfig,
+        settle: bool,
+    ) -> TridentTransactionProcessingResult {
+        let tx_processing_environment = TransactionProcessingEnvironment::<'_> {
+            feature_set: *self.feature_set,
+            ..Default::default()
+        };
 
         // reset sysvar cache
         self.processor.reset_sysvar_cache();
@@ -79,66 +67,136 @@ impl TridentSVM {
         // replenish sysvar cache with sysvars from the accounts db
         self.processor.fill_missing_sysvar_cache_entries(self);
 
+        // keep the transaction if it is recorded
+        let recorded_transaction = self
+            .transaction_recording
+            .is_some()
+            .then(|| transaction.clone());
+
+        // programs invoked through CPI are among the accounts of the transaction
+        let loaded_accounts = transaction.message.account_keys.clone();
+
         // create sanitized transaction
         let sanitezed_tx =
             SanitizedTransaction::try_from_legacy_transaction(transaction, &HashSet::new())
                 .expect("Trident SVM is not able to create sanitized transaction");
 
         // get current transaction timestamp
-        let transaction_timestamp =
-            self.accounts.deserialize_sysvar::<Clock>().unix_timestamp as u64;
+        let clock = self.accounts.deserialize_sysvar::<Clock>();
+        let transaction_timestamp = clock.unix_timestamp as u64;
 
         // execute transaction
+        let mocks = ActiveMocks::install(&self.mocks);
+        let syscall_faults = ActiveSyscallFaults::install(&self.fault_injection.syscall_faults);
+        #[cfg(feature = "syscall-v2")]
+        let native_programs = ActiveNativePrograms::install(&self.native_programs);
         let result = self.processor.load_and_execute_sanitized_transactions(
             self,
             &[sanitezed_tx],
-            get_transaction_check_results(1),
+            get_transaction_check_results(1, &self.fault_injection),
             &tx_processing_environment,
             &tx_processing_config,
         );
+        #[cfg(feature = "syscall-v2")]
+        drop(native_programs);
+        drop(syscall_faults);
+        drop(mocks);
 
         // update clock
         self.accounts.update_clock();
 
-        let processed_transaction = result.processing_results[0]
-            .processed_transaction()
-            .expect("Transaction was not processed");
+        // collect coverage and compute units of the executed programs
+        self.collect_execution_record();
 
-        match &processed_transaction {
-            solana_svm::transaction_processing_result::ProcessedTransaction::Executed(
-                executed_tx,
-            ) => match &executed_tx.execution_details.status {
-                Ok(()) => {
+        // transactions which fail to load, e.g. without a fee payer, are not processed
+        if settle {
+            if let Some(ProcessedTransaction::Executed(executed_tx)) =
+                result.processing_results[0].processed_transaction()
+            {
+                // in case of transaction error, we don't need to do anything
+                if executed_tx.execution_details.status.is_ok() {
                     self.settle_accounts(&executed_tx.loaded_transaction.accounts);
                 }
-                Err(_transaction_error) => {
-                    // in case of transaction error, we don't need to do anything
-                }
-            },
-            solana_svm::transaction_processing_result::ProcessedTransaction::FeesOnly(
-                _transaction_error,
-            ) => {
-                // in case of transaction error, we don't need to do anything
             }
         }
+
+        if let Some(transaction) = recorded_transaction {
+            self.record_transaction(transaction, clock, settle, &result);
+        }
+
+        // return transaction processing result
+        let execution_modes = self.execution_modes(&loaded_accounts);
         TridentTransactionProcessingResult::new(result, transaction_timestamp)
+            .with_execution_modes(execution_modes)
+    }
+
+    /// Returns the mode each of the SBF programs was executed in, as compiled
+    /// for the current environment of the program cache.
+    fn execution_modes(&self, program_ids: &[Pubkey]) -> HashMap<Pubkey, ExecutionMode> {
+        let cache = self
+            .processor
+            .program_cache
+            .read()
+            .expect("Failed to read program cache");
+        let environment = &cache.environments.program_runtime_v1;
+        cache
+            .get_flattened_entries(true, false)
+            .into_iter()
+            .filter(|(program_id, _)| program_ids.contains(program_id))
+            .filter_map(|(program_id, entry)| match &entry.program {
+                ProgramCacheEntryType::Loaded(executable)
+                    if Arc::ptr_eq(executable.get_loader(), environment) =>
+                {
+                    #[cfg(all(not(target_os = "windows"), target_arch = "x86_64"))]
+                    let is_jit_compiled = executable.get_compiled_program().is_some();
+                    #[cfg(not(all(not(target_os = "windows"), target_arch = "x86_64")))]
+                    let is_jit_compiled = false;
+                    let mode = if is_jit_compiled {
+                        ExecutionMode::Jit
+                    } else {
+                        ExecutionMode::Interpreter
+                    };
+                    Some((program_id, mode))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn collect_execution_record(&mut self) {
+        if !self.is_instrumented() {
+            return;
+        }
+        let record = take_execution_record();
+        self.record_sbf_coverage(&record);
+        self.record_compute_units(&record);
     }
 }
 
 /// This function is also a mock. In the Agave validator, the bank pre-checks
 /// transactions before providing them to the SVM API. We mock this step in
 /// PayTube, since we don't need to perform such pre-checks.
+///
+/// The execution budget is limited as requested by the fault injection.
 pub(crate) fn get_transaction_check_results(
     len: usize,
+    fault_injection: &FaultInjection,
 ) -> Vec<solana_transaction_error::TransactionResult<CheckedTransactionDetails>> {
     let compute_budget_limit = ComputeBudgetLimits::default();
+    let mut compute_budget_and_limits = compute_budget_limit.get_compute_budget_and_limits(
+        compute_budget_limit.loaded_accounts_bytes,
+        FeeDetails::default(),
+    );
+    if let Some(depth) = fault_injection.max_instruction_stack_depth {
+        compute_budget_and_limits.budget.max_instruction_stack_depth = depth;
+    }
+    if let Some(units) = fault_injection.compute_unit_limit {
+        compute_budget_and_limits.budget.compute_unit_limit = units;
+    }
     vec![
         solana_transaction_error::TransactionResult::Ok(CheckedTransactionDetails::new(
             None,
-            Ok(compute_budget_limit.get_compute_budget_and_limits(
-                compute_budget_limit.loaded_accounts_bytes,
-                FeeDetails::default()
-            )),
+            Ok(compute_budget_and_limits),
         ));
         len
     ]
@@ -0,0 +1,139 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use solana_account::AccountSharedData;
+use solana_account::ReadableAccount;
+use solana_instruction::error::InstructionError;
+use solana_program_runtime::invoke_context::InvokeContext;
+use solana_program_runtime::loaded_programs::ProgramCacheEntry;
+use solana_program_runtime::solana_sbpf::error::EbpfError;
+use solana_program_runtime::solana_sbpf::vm::EbpfVm;
+use solana_pubkey::Pubkey;
+
+use crate::instrumentation::vm_from_ptr;
+use crate::types::program_mock::MockAccount;
+use crate::types::program_mock::MockInvocation;
+use crate::types::program_mock::ProgramMock;
+
+/// Name of the builtin which dispatches to the mocks.
+pub(crate) const MOCK_PROGRAM_NAME: &str = "trident_mock";
+
+/// A mocked program together with what is restored once the mock is removed.
+#[derive(Clone)]
+pub(crate) struct MockedProgram {
+    pub(crate) mock: Arc<ProgramMock>,
+    pub(crate) original_account: Option<AccountSharedData>,
+    /// Entry of the builtin replaced by the mock.
+    pub(crate) original_builtin: Option<Arc<ProgramCacheEntry>>,
+}
+
+thread_local! {
+    // Builtins are plain function pointers, so the mocks of the TridentSVM which processes
+    // the transaction are handed over through a thread local, see `EXECUTION_RECORD`.
+    static ACTIVE_MOCKS: RefCell<HashMap<Pubkey, Arc<ProgramMock>>> = RefCell::new(HashMap::new());
+}
+
+/// Makes the mocks available to the mock builtin until the guard is dropped.
+pub(crate) struct ActiveMocks {
+    previous: HashMap<Pubkey, Arc<ProgramMock>>,
+}
+
+impl ActiveMocks {
+    pub(crate) fn install(mocks: &HashMap<Pubkey, MockedProgram>) -> Self {
+        let mocks = mocks
+            .iter()
+            .map(|(program_id, mocked)| (*program_id, Arc::clone(&mocked.mock)))
+            .collect();
+        Self {
+            previous: ACTIVE_MOCKS.replace(mocks),
+        }
+    }
+}
+
+impl Drop for ActiveMocks {
+    fn drop(&mut self) {
+        ACTIVE_MOCKS.set(std::mem::take(&mut self.previous));
+    }
+}
+
+/// Builtin entrypoint of all mocked programs, executes the mock of the invoked program.
+pub(crate) fn mock_entrypoint(
+    vm: *mut EbpfVm<InvokeContext<'static>>,
+    _arg0: u64,
+    _arg1: u64,
+    _arg2: u64,
+    _arg3: u64,
+    _arg4: u64,
+) {
+    let vm = unsafe { vm_from_ptr(vm) };
+    vm.program_result = invoke_mock(vm.context_object_pointer)
+        .map(|_| 0)
+        .map_err(|err| EbpfError::SyscallError(Box::new(err)))
+        .into();
+}
+
+fn invoke_mock(invoke_context: &mut InvokeContext) -> Result<(), InstructionError> {
+    let mut invocation = {
+        let transaction_context = &invoke_context.transaction_context;
+        let instruction_context = transaction_context.get_current_instruction_context()?;
+        let program_id = *instruction_context.get_last_program_key(transaction_context)?;
+
+        let mut accounts = Vec::new();
+        for index in 0..instruction_context.get_number_of_instruction_accounts() {
+            let index_in_transaction =
+                instruction_context.get_index_of_instruction_account_in_transaction(index)?;
+            accounts.push(MockAccount {
+                address: *transaction_context.get_key_of_account_at_index(index_in_transaction)?,
+                is_signer: instruction_context.is_instruction_account_signer(index)?,
+                is_writable: instruction_context.is_instruction_account_writable(index)?,
+                account: transaction_context
+                    .accounts()
+                    .try_borrow(index_in_transaction)?
+                    .clone(),
+            });
+        }
+
+        MockInvocation {
+            program_id,
+            accounts,
+            data: instruction_context.get_instruction_data().to_vec(),
+            stack_height: invoke_context.get_stack_height(),
+            compute_units: 1,
+            return_data: None,
+        }
+    };
+
+    let mock = ACTIVE_MOCKS
+        .with_borrow(|mocks| mocks.get(&invocation.program_id).cloned())
+        .ok_or(InstructionError::UnsupportedProgramId)?;
+    mock(&mut invocation)?;
+    // builtins are required to consume compute units
+    invoke_context
+        .consume_checked(invocation.compute_units.max(1))
+        .map_err(|_| InstructionError::ComputationalBudgetExceeded)?;
+
+    // the changes are applied in the order the SBF loader deserializes them
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    for (index, mocked) in (0..).zip(&invocation.accounts) {
+        let mut account =
+            instruction_context.try_borrow_instruction_account(transaction_context, index)?;
+        if account.get_lamports() != mocked.account.lamports() {
+            account.set_lamports(mocked.account.lamports())?;
+        }
+        if account.get_data() != mocked.account.data() {
+            account.set_data_from_slice(mocked.account.data())?;
+        }
+        if account.get_owner() != mocked.account.owner() {
+            account.set_owner(mocked.account.owner().as_ref())?;
+        }
+    }
+
+    if let Some(return_data) = invocation.return_data {
+        invoke_context
+            .transaction_context
+            .set_return_data(invocation.program_id, return_data)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+use solana_bpf_loader_program::syscalls::create_program_runtime_environment_v1;
+use solana_hash::Hash;
+use solana_program_runtime::execution_budget::SVMTransactionExecutionBudget;
+use solana_program_runtime::loaded_programs::ProgramCacheEntry;
+use solana_program_runtime::loaded_programs::ProgramCacheEntryOwner;
+use solana_program_runtime::loaded_programs::ProgramCacheEntryType;
+use solana_program_runtime::loaded_programs::ProgramRuntimeEnvironment;
+use solana_program_runtime::solana_sbpf::elf::Executable;
+use solana_program_runtime::solana_sbpf::verifier::RequisiteVerifier;
+use solana_pubkey::Pubkey;
+use solana_svm_feature_set::SVMFeatureSet;
+
+use crate::instrumentation::syscalls::inject_syscall_faults;
+use crate::instrumentation::syscalls::instrument_syscalls;
+
+/// Configuration of the v1 runtime environment an executable is compiled for.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct EnvironmentKey {
+    /// Instruction tracing, required to collect SBF coverage and CU profiles.
+    pub(crate) tracing: bool,
+    /// Syscalls recording the compute units they consume.
+    pub(crate) instrumented_syscalls: bool,
+    /// Programs are JIT compiled, resolved against the platform support.
+    pub(crate) jit: bool,
+    /// Syscalls failing as configured by the fault injection.
+    pub(crate) syscall_faults: bool,
+}
+
+/// The environments are shared by all instances, so the program cache
+/// of every instance accepts the entries compiled by any other one.
+static ENVIRONMENTS: LazyLock<Mutex<HashMap<EnvironmentKey, ProgramRuntimeEnvironment>>> =
+    LazyLock::new(Default::default);
+
+/// Hash of the ELF, the loader and the environment of a compiled program.
+type ProgramKey = (Hash, Pubkey, EnvironmentKey);
+
+/// Compiled programs kept by the process wide cache. Upgraded and mutated programs
+/// would otherwise be kept for the whole process, so the least recently used ones
+/// are dropped, instances which still execute them keep their own reference.
+const MAX_COMPILED_PROGRAMS: usize = 64;
+
+/// Verified and, where supported, JIT compiled programs.
+static PROGRAMS: LazyLock<Mutex<CompiledPrograms>> =
+    LazyLock::new(|| Mutex::new(CompiledPrograms::new(MAX_COMPILED_PROGRAMS)));
+
+/// Least recently used cache of compiled programs.
+struct CompiledPrograms {
+    capacity: usize,
+    /// Entries with the tick of their last use.
+    entries: HashMap<ProgramKey, (Arc<ProgramCacheEntry>, u64)>,
+    tick: u64,
+}
+
+impl CompiledPrograms {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, key: &ProgramKey) -> Option<Arc<ProgramCacheEntry>> {
+        self.tick += 1;
+        let (entry, last_used) = self.entries.get_mut(key)?;
+        *last_used = self.tick;
+        Some(Arc::clone(entry))
+    }
+
+    /// Inserts the entry unless the key is present already, returns the cached entry.
+    fn insert(&mut self, key: ProgramKey, entry: Arc<ProgramCacheEntry>) -> Arc<ProgramCacheEntry> {
+        if let Some(cached) = self.get(&key) {
+            return cached;
+        }
+        if self.entries.len() >= self.capacity {
+            let least_recently_used = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key);
+            if let Some(key) = least_recently_used {
+                self.entries.remove(&key);
+            }
+        }
+        self.entries.insert(key, (Arc::clone(&entry), self.tick));
+        entry
+    }
+}
+
+/// Returns the v1 runtime environment of the given configuration.
+pub(crate) fn program_runtime_environment_v1(
+    key: EnvironmentKey,
+    feature_set: &SVMFeatureSet,
+    compute_budget: &SVMTransactionExecutionBudget,
+) -> ProgramRuntimeEnvironment {
+    let mut environments = ENVIRONMENTS.lock().expect("Environments lock poisoned");
+    let environment = environments.entry(key).or_insert_with(|| {
+        let program_runtime_v1 =
+            create_program_runtime_environment_v1(feature_set, compute_budget, false, key.tracing)
+                .expect("Failed to create program runtime environment");
+        let program_runtime_v1 = if key.instrumented_syscalls {
+            instrument_syscalls(&program_runtime_v1)
+        } else {
+            program_runtime_v1
+        };
+        Arc::new(if key.syscall_faults {
+            inject_syscall_faults(&program_runtime_v1)
+        } else {
+            program_runtime_v1
+        })
+    });
+    Arc::clone(environment)
+}
+
+/// Returns the compiled program, the ELF is verified and JIT compiled only
+/// the first time it is requested in the process.
+///
+/// Returns `None` if the ELF fails the verification, such programs are left
+/// to the program loader which caches them as failed.
+pub(crate) fn compiled_program(
+    key: EnvironmentKey,
+    environment: &ProgramRuntimeEnvironment,
+    loader: &Pubkey,
+    elf: &[u8],
+    account_size: usize,
+) -> Option<Arc<ProgramCacheEntry>> {
+    let cache_key = (solana_sha256_hasher::hash(elf), *loader, key);
+    if let Some(entry) = PROGRAMS
+        .lock()
+        .expect("Programs lock poisoned")
+        .get(&cache_key)
+    {
+        return Some(entry);
+    }
+
+    // compiled outside of the lock, a concurrent compilation of the same ELF is merely wasted
+    let entry = Arc::new(compile(key, environment, loader, elf, account_size)?);
+    let mut programs = PROGRAMS.lock().expect("Programs lock poisoned");
+    Some(programs.insert(cache_key, entry))
+}
+
+/// Verifies the ELF and JIT compiles it if requested, programs which fail
+/// to compile are interpreted.
+fn compile(
+    key: EnvironmentKey,
+    environment: &ProgramRuntimeEnvironment,
+    loader: &Pubkey,
+    elf: &[u8],
+    account_size: usize,
+) -> Option<ProgramCacheEntry> {
+    #[allow(unused_mut)]
+    let mut executable = Executable::load(elf, Arc::clone(environment)).ok()?;
+    executable.verify::<RequisiteVerifier>().ok()?;
+
+    #[cfg(all(not(target_os = "windows"), target_arch = "x86_64"))]
+    if key.jit {
+        if let Err(err) = executable.jit_compile() {
+            log::warn!("Failed to JIT compile a program of {loader}, it is interpreted: {err}");
+        }
+    }
+
+    Some(ProgramCacheEntry {
+        program: ProgramCacheEntryType::Loaded(executable),
+        account_owner: ProgramCacheEntryOwner::try_from(loader).ok()?,
+        account_size,
+        deployment_slot: 0,
+        effective_slot: 0,
+        tx_usage_counter: AtomicU64::new(0),
+        ix_usage_counter: AtomicU64::new(0),
+        latest_access_slot: AtomicU64::new(0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use solana_program_runtime::loaded_programs::ProgramCacheEntry;
+    use solana_program_runtime::loaded_programs::ProgramCacheEntryOwner;
+    use solana_program_runtime::loaded_programs::ProgramCacheEntryType;
+
+    use super::CompiledPrograms;
+    use super::EnvironmentKey;
+    use super::ProgramKey;
+
+    fn program_key(elf: &[u8]) -> ProgramKey {
+        let environment = EnvironmentKey {
+            tracing: false,
+            instrumented_syscalls: false,
+            jit: false,
+            syscall_faults: false,
+        };
+        (
+            solana_sha256_hasher::hash(elf),
+            solana_sdk_ids::bpf_loader_upgradeable::id(),
+            environment,
+        )
+    }
+
+    fn entry() -> Arc<ProgramCacheEntry> {
+        Arc::new(ProgramCacheEntry::new_tombstone(
+            0,
+            ProgramCacheEntryOwner::LoaderV3,
+            ProgramCacheEntryType::Closed,
+        ))
+    }
+
+    #[test]
+    fn test_least_recently_used_programs_are_dropped() {
+        let mut programs = CompiledPrograms::new(2);
+        let first = programs.insert(program_key(b"first"), entry());
+        programs.insert(program_key(b"second"), entry());
+
+        // a concurrently compiled entry of the same program is replaced by the cached one
+        assert!(Arc::ptr_eq(
+            &programs.insert(program_key(b"first"), entry()),
+            &first
+        ));
+
+        programs.insert(program_key(b"third"), entry());
+        assert!(programs.get(&program_key(b"first")).is_some());
+        assert!(programs.get(&program_key(b"second")).is_none());
+        assert!(programs.get(&program_key(b"third")).is_some());
+        assert_eq!(programs.entries.len(), 2);
+    }
+}
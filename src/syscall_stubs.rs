@@ -0,0 +1,106 @@
+use std::sync::Once;
+
+use solana_account_info::AccountInfo;
+use solana_instruction::error::InstructionError;
+use solana_instruction::Instruction;
+use solana_program_error::ProgramResult;
+use solana_program_runtime::stable_log;
+use solana_pubkey::Pubkey;
+use solana_sysvar::program_stubs::set_syscall_stubs;
+use solana_sysvar::program_stubs::SyscallStubs;
+use trident_syscall_stubs_v2::get_invoke_context;
+use trident_syscall_stubs_v2::TridentSyscallStubs;
+
+/// Maximum size of the return data, the same as `MAX_RETURN_DATA` of `solana-cpi`.
+const MAX_RETURN_DATA: usize = 1024;
+
+static STUBS: Once = Once::new();
+
+/// Installs the syscall stubs used by native programs, once per process.
+pub(crate) fn set_native_syscall_stubs() {
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(NativeSyscallStubs(TridentSyscallStubs)));
+    });
+}
+
+/// Syscall stubs of native programs, the return data behaves as the return data syscalls
+/// of SBF programs and the remaining syscalls are handled by [`TridentSyscallStubs`].
+struct NativeSyscallStubs(TridentSyscallStubs);
+
+impl SyscallStubs for NativeSyscallStubs {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_rewards_sysvar(var_addr)
+    }
+
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_last_restart_slot(var_addr)
+    }
+
+    /// Empty return data reads as no return data, the same as `sol_get_return_data`
+    /// returning a zero length.
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        let (program_id, data) = get_invoke_context().transaction_context.get_return_data();
+        (!data.is_empty()).then(|| (*program_id, data.to_vec()))
+    }
+
+    /// Errors abort SBF programs, so they unwind the native program with the error
+    /// as the panic payload, see [`crate::processor::panic_error`].
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let invoke_context = get_invoke_context();
+        if data.len() > MAX_RETURN_DATA {
+            // the SBF loader fails the program on syscall errors other than instruction errors
+            stable_log::program_log(
+                &invoke_context.get_log_collector(),
+                &format!("Return data too large ({} > {MAX_RETURN_DATA})", data.len()),
+            );
+            std::panic::panic_any(InstructionError::ProgramFailedToComplete);
+        }
+
+        let transaction_context = &mut invoke_context.transaction_context;
+        let result = transaction_context
+            .get_current_instruction_context()
+            .and_then(|instruction_context| {
+                instruction_context
+                    .get_last_program_key(transaction_context)
+                    .copied()
+            })
+            .and_then(|program_id| transaction_context.set_return_data(program_id, data.to_vec()));
+        if let Err(err) = result {
+            std::panic::panic_any(err);
+        }
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
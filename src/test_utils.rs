@@ -0,0 +1,38 @@
+use solana_account::AccountSharedData;
+use solana_hash::Hash;
+use solana_instruction::AccountMeta;
+use solana_instruction::Instruction;
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+use solana_rent::Rent;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::trident_svm::TridentSVM;
+
+/// Stores an uninitialized mint owned by the token program and returns it together with
+/// an InitializeMint2 transaction with 6 decimals and no freeze authority, signed by the payer.
+pub(crate) fn initialize_mint_transaction(
+    svm: &mut TridentSVM,
+    token_program: &Pubkey,
+) -> (Pubkey, Transaction) {
+    let payer = svm.get_payer();
+    let mint = Keypair::new().pubkey();
+    let mint_account =
+        AccountSharedData::new(Rent::default().minimum_balance(82), 82, token_program);
+    svm.set_account(&mint, &mint_account, false);
+
+    let mut data = vec![20, 6];
+    data.extend_from_slice(payer.pubkey().as_ref());
+    data.push(0);
+    let instruction =
+        Instruction::new_with_bytes(*token_program, &data, vec![AccountMeta::new(mint, false)]);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[&payer],
+        Hash::default(),
+    );
+
+    (mint, transaction)
+}
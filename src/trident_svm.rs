@@ -1,9 +1,10 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::RwLock;
 
-use solana_bpf_loader_program::syscalls::create_program_runtime_environment_v1;
 use solana_bpf_loader_program::syscalls::create_program_runtime_environment_v2;
 
+use solana_program_runtime::loaded_programs::ProgramCache;
 use solana_program_runtime::loaded_programs::ProgramCacheEntry;
 
 use solana_account::AccountSharedData;
@@ -13,7 +14,6 @@ use solana_epoch_rewards::EpochRewards;
 use solana_epoch_schedule::EpochSchedule;
 use solana_hash::Hash;
 use solana_keypair::Keypair;
-use solana_pubkey::pubkey;
 use solana_pubkey::Pubkey;
 use solana_rent::Rent;
 use solana_signer::Signer;
@@ -34,11 +34,16 @@ use solana_svm::transaction_processing_callback::TransactionProcessingCallback;
 use solana_svm::transaction_processor::TransactionBatchProcessor;
 
 use solana_svm_callback::InvokeContextCallback;
-#[cfg(feature = "syscall-v2")]
-use trident_syscall_stubs_v2::set_stubs_v2;
 
 use crate::accounts_database::accounts_db::AccountsDB;
 use crate::builder::TridentSVMBuilder;
+use crate::instrumentation::loader::InstrumentedLoaderEntrypoint;
+use crate::instrumentation::profiler::ComputeUnitProfiler;
+use crate::mocking::MockedProgram;
+use crate::program_cache::program_runtime_environment_v1;
+use crate::program_cache::EnvironmentKey;
+#[cfg(feature = "syscall-v2")]
+use crate::syscall_stubs::set_native_syscall_stubs;
 
 use crate::trident_fork_graphs::TridentForkGraph;
 use crate::utils;
@@ -46,21 +51,60 @@ use solana_builtins::BUILTINS;
 
 use solana_program_runtime::execution_budget::SVMTransactionExecutionBudget;
 
+use crate::types::bundled_program::BundledProgram;
+use crate::types::execution_mode::ExecutionMode;
+use crate::types::fault_injection::FaultInjection;
+use crate::types::sbf_coverage::SbfCoverage;
+use crate::types::transaction_recording::TransactionRecording;
+#[cfg(feature = "syscall-v2")]
+use crate::types::trident_entrypoint::NativeEntrypoint;
 use crate::types::trident_program::TridentProgram;
 use crate::utils::get_current_timestamp;
 
+/// Seeded timestamps start at 2024-01-01.
+const SEEDED_TIMESTAMP_START: i64 = 1_704_067_200;
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+/// Balance of the default payer.
+pub(crate) const PAYER_LAMPORTS: u64 = 500_000_000 * 1_000_000_000;
+
 pub struct TridentSVM {
     pub(crate) accounts: AccountsDB,
     pub(crate) payer: Keypair,
+    /// Seed of the builder, keypairs created by `create_funded_keypair` are random if `None`.
+    pub(crate) keypair_seed: Option<u64>,
+    pub(crate) derived_keypairs: u64,
     pub(crate) feature_set: Arc<SVMFeatureSet>,
     pub(crate) processor: TransactionBatchProcessor<TridentForkGraph>,
     pub(crate) fork_graph: Arc<RwLock<TridentForkGraph>>,
+    pub(crate) sbf_coverage: Option<SbfCoverage>,
+    pub(crate) cu_profiler: Option<ComputeUnitProfiler>,
+    /// Resolved against the platform support, see [`ExecutionMode::resolve`].
+    pub(crate) execution_mode: ExecutionMode,
+    pub(crate) mocks: HashMap<Pubkey, MockedProgram>,
+    /// Entries of the builtins, native programs and mocks in the program cache, which
+    /// are added again when the program cache is rebuilt.
+    pub(crate) builtins: HashMap<Pubkey, Arc<ProgramCacheEntry>>,
+    pub(crate) fault_injection: FaultInjection,
+    pub(crate) transaction_recording: Option<TransactionRecording>,
+    #[cfg(feature = "syscall-v2")]
+    pub(crate) native_programs: HashMap<Pubkey, NativeEntrypoint>,
+    /// Whether the syscall stubs of native programs are installed.
+    #[cfg(feature = "syscall-v2")]
+    pub(crate) syscalls_v2: bool,
 }
 
+// Workers are moved to and shared between threads, see `TridentSVM::new_worker`.
+const _: fn() = || {
+    fn assert<T: Send + Sync>() {}
+    assert::<TridentSVM>();
+};
+
 impl TridentSVM {
     #[cfg(feature = "syscall-v2")]
     pub(crate) fn initialize_syscalls_v2(&mut self) {
-        set_stubs_v2();
+        set_native_syscall_stubs();
+        self.syscalls_v2 = true;
     }
 }
 
@@ -73,13 +117,29 @@ impl TransactionProcessingCallback for TridentSVM {
     }
 
     fn get_account_shared_data(&self, pubkey: &Pubkey) -> Option<AccountSharedData> {
+        if self.fault_injection.is_missing_account(pubkey) {
+            return None;
+        }
         self.accounts.get_account(pubkey, false)
     }
 }
 
 impl Default for TridentSVM {
     fn default() -> Self {
-        let payer = Keypair::new();
+        Self::without_programs(None).with_solana_program_library(&BundledProgram::ALL)
+    }
+}
+
+impl TridentSVM {
+    /// Creates the SVM with sysvars and builtins, but without any bundled programs.
+    ///
+    /// With a seed, the payer, the initial timestamp and the blockhashes are derived
+    /// from it and the clock does not follow the system time.
+    pub(crate) fn without_programs(seed: Option<u64>) -> Self {
+        let payer = match seed {
+            Some(seed) => Keypair::new_from_array(derive_from_seed(seed, b"payer").to_bytes()),
+            None => Keypair::new(),
+        };
 
         let feature_set = SVMFeatureSet {
             enable_sbpf_v1_deployment_and_execution: true,
@@ -91,6 +151,8 @@ impl Default for TridentSVM {
         let mut client = Self {
             accounts: Default::default(),
             payer: payer.insecure_clone(),
+            keypair_seed: seed,
+            derived_keypairs: 0,
             feature_set: Arc::new(feature_set),
             processor: TransactionBatchProcessor::<TridentForkGraph>::new(
                 1,
@@ -100,22 +162,32 @@ impl Default for TridentSVM {
                 None,
             ),
             fork_graph: Arc::new(RwLock::new(TridentForkGraph {})),
+            sbf_coverage: None,
+            cu_profiler: None,
+            execution_mode: ExecutionMode::default().resolve(),
+            mocks: HashMap::new(),
+            builtins: HashMap::new(),
+            fault_injection: FaultInjection::default(),
+            transaction_recording: None,
+            #[cfg(feature = "syscall-v2")]
+            native_programs: HashMap::new(),
+            #[cfg(feature = "syscall-v2")]
+            syscalls_v2: false,
         };
 
-        let payer_account = AccountSharedData::new(
-            500_000_000 * 1_000_000_000,
-            0,
-            &solana_sdk_ids::system_program::id(),
-        );
+        let payer_account =
+            AccountSharedData::new(PAYER_LAMPORTS, 0, &solana_sdk_ids::system_program::id());
         client
             .accounts
             .set_permanent_account(&payer.pubkey(), &payer_account);
-
         client
-            .with_processor()
-            .with_sysvars()
-            .with_builtins()
-            .with_solana_program_library()
+            .accounts
+            .sysvar_tracker
+            .get_mut()
+            .expect("Sysvar tracker lock poisoned")
+            .deterministic = seed.is_some();
+
+        client.with_processor().with_sysvars(seed).with_builtins()
     }
 }
 
@@ -123,40 +195,229 @@ impl TridentSVM {
     pub fn builder() -> TridentSVMBuilder {
         TridentSVMBuilder::new()
     }
+
+    /// Creates an instance which shares the program cache with this one, so programs
+    /// compiled by any of them are reused by all. Accounts are copied and diverge from then on.
+    ///
+    /// Programs should be deployed before workers are created, the slot at which deployed
+    /// programs become visible is not synchronized between the instances.
+    pub fn new_worker(&self) -> TridentSVM {
+        let slot = self
+            .processor
+            .program_cache
+            .read()
+            .expect("Failed to read program cache")
+            .latest_root_slot;
+        let epoch = self.accounts.get_sysvar::<Clock>().epoch;
+
+        TridentSVM {
+            accounts: self.accounts.clone(),
+            payer: self.payer.insecure_clone(),
+            keypair_seed: self.keypair_seed,
+            derived_keypairs: self.derived_keypairs,
+            feature_set: Arc::clone(&self.feature_set),
+            processor: self.processor.new_from(slot, epoch),
+            fork_graph: Arc::clone(&self.fork_graph),
+            sbf_coverage: self.sbf_coverage.as_ref().map(|_| SbfCoverage::default()),
+            cu_profiler: self
+                .cu_profiler
+                .as_ref()
+                .map(|_| ComputeUnitProfiler::default()),
+            execution_mode: self.execution_mode,
+            mocks: self.mocks.clone(),
+            builtins: self.builtins.clone(),
+            fault_injection: self.fault_injection.clone(),
+            transaction_recording: self
+                .transaction_recording
+                .as_ref()
+                .map(|_| TransactionRecording::default()),
+            #[cfg(feature = "syscall-v2")]
+            native_programs: self.native_programs.clone(),
+            #[cfg(feature = "syscall-v2")]
+            syscalls_v2: self.syscalls_v2,
+        }
+    }
+
+    /// Creates an instance with a copy of the accounts and deployed programs of this one.
+    ///
+    /// Unlike workers, the program cache is not shared, so programs can be redeployed or
+    /// upgraded in either instance without affecting the other. Compiled programs are reused.
+    pub fn fork(&self) -> TridentSVM {
+        let mut fork = self.new_worker();
+        fork.rebuild_program_cache();
+        fork
+    }
+
     fn with_processor(self) -> Self {
-        {
-            let compute_budget = SVMTransactionExecutionBudget::default();
+        self.configure_program_runtime_environments();
+        self
+    }
+
+    pub(crate) fn configure_program_runtime_environments(&self) {
+        let compute_budget = SVMTransactionExecutionBudget::default();
+
+        let mut cache: std::sync::RwLockWriteGuard<
+            '_,
+            solana_program_runtime::loaded_programs::ProgramCache<TridentForkGraph>,
+        > = self
+            .processor
+            .program_cache
+            .write()
+            .expect("Failed to write to program cache");
+
+        cache.fork_graph = Some(Arc::downgrade(&self.fork_graph));
+
+        cache.environments.program_runtime_v1 = program_runtime_environment_v1(
+            self.environment_key(),
+            &self.feature_set,
+            &compute_budget,
+        );
+        cache.environments.program_runtime_v2 =
+            Arc::new(create_program_runtime_environment_v2(&compute_budget, true));
+    }
+
+    pub(crate) fn environment_key(&self) -> EnvironmentKey {
+        EnvironmentKey {
+            tracing: self.is_instrumented(),
+            instrumented_syscalls: self.cu_profiler.is_some(),
+            jit: self.execution_mode == ExecutionMode::Jit,
+            syscall_faults: !self.fault_injection.syscall_faults.is_empty(),
+        }
+    }
+
+    pub(crate) fn is_instrumented(&self) -> bool {
+        self.sbf_coverage.is_some() || self.cu_profiler.is_some()
+    }
+
+    /// Replaces the SBF loaders with entrypoints which record the execution of SBF programs.
+    pub(crate) fn instrument_program_execution(&mut self) {
+        self.reload_programs();
+
+        BUILTINS
+            .iter()
+            .filter(|builtin| is_sbf_loader(&builtin.program_id))
+            .for_each(|builtin| {
+                self.add_builtin(
+                    builtin.program_id,
+                    ProgramCacheEntry::new_builtin(
+                        0,
+                        builtin.name.len(),
+                        InstrumentedLoaderEntrypoint::vm,
+                    ),
+                );
+            });
+    }
+
+    /// Adds the builtin to the program cache, replacing the entries of the program.
+    pub(crate) fn add_builtin(&mut self, program_id: Pubkey, entry: ProgramCacheEntry) {
+        let entry = Arc::new(entry);
+        self.processor
+            .builtin_program_ids
+            .write()
+            .expect("Failed to write builtin program ids")
+            .insert(program_id);
+        self.processor
+            .program_cache
+            .write()
+            .expect("Failed to write to program cache")
+            .assign_program(program_id, Arc::clone(&entry));
+        self.builtins.insert(program_id, entry);
+    }
+
+    /// Whether the program cache is shared with workers, see [`TridentSVM::new_worker`].
+    pub(crate) fn is_program_cache_shared(&self) -> bool {
+        Arc::strong_count(&self.processor.program_cache) > 1
+    }
 
-            let mut cache: std::sync::RwLockWriteGuard<
-                '_,
-                solana_program_runtime::loaded_programs::ProgramCache<TridentForkGraph>,
-            > = self
+    /// Replaces the program cache with a new one holding the builtins and the deployed
+    /// programs, compiled for the current environment.
+    ///
+    /// Programs deployed through loader instructions are compiled again from their accounts.
+    pub(crate) fn rebuild_program_cache(&mut self) {
+        let (slot, epoch) = {
+            let cache = self
                 .processor
                 .program_cache
-                .write()
-                .expect("Failed to write to program cache");
+                .read()
+                .expect("Failed to read program cache");
+            (cache.latest_root_slot, cache.latest_root_epoch)
+        };
+        self.processor.program_cache = Arc::new(RwLock::new(ProgramCache::new(slot, epoch)));
+        self.configure_program_runtime_environments();
+
+        let mut cache = self
+            .processor
+            .program_cache
+            .write()
+            .expect("Failed to write to program cache");
+        for (program_id, entry) in &self.builtins {
+            cache.assign_program(*program_id, Arc::clone(entry));
+        }
+        drop(cache);
+        for program_id in self.deployed_sbf_programs() {
+            self.invalidate_program(&program_id);
+        }
+    }
 
-            cache.fork_graph = Some(Arc::downgrade(&self.fork_graph));
+    /// Selects whether SBF programs are interpreted or JIT compiled, JIT falls back
+    /// to the interpreter on platforms which do not support it.
+    ///
+    /// Programs deployed through loader instructions of processed transactions are
+    /// compiled by the loader itself until they are redeployed or upgraded through
+    /// the methods of [`TridentSVM`].
+    ///
+    /// The mode belongs to the program cache, so an instance sharing the cache with
+    /// workers gets its own copy of the cache and the workers keep their mode.
+    pub fn set_execution_mode(&mut self, mode: ExecutionMode) {
+        let mode = mode.resolve();
+        if self.execution_mode != mode {
+            self.execution_mode = mode;
+            self.reload_programs();
+        }
+    }
 
-            cache.environments.program_runtime_v1 = Arc::new(
-                create_program_runtime_environment_v1(
-                    &self.feature_set,
-                    &compute_budget,
-                    false,
-                    false,
-                )
-                .expect("Failed to create program runtime environment"),
-            );
-            cache.environments.program_runtime_v2 =
-                Arc::new(create_program_runtime_environment_v2(&compute_budget, true));
+    /// Returns the mode SBF programs are executed in.
+    pub fn get_execution_mode(&self) -> ExecutionMode {
+        self.execution_mode
+    }
+
+    /// Reconfigures the runtime environment, programs compiled for the previous
+    /// environment are no longer used and the deployed ones are loaded again.
+    ///
+    /// A program cache shared with workers is not modified, the instance gets its
+    /// own cache instead, see [`TridentSVM::rebuild_program_cache`].
+    pub(crate) fn reload_programs(&mut self) {
+        if self.is_program_cache_shared() {
+            self.rebuild_program_cache();
+            return;
+        }
+        self.configure_program_runtime_environments();
+        for program_id in self.deployed_sbf_programs() {
+            self.invalidate_program(&program_id);
         }
+    }
 
-        self
+    fn deployed_sbf_programs(&self) -> Vec<Pubkey> {
+        self.accounts
+            .programs
+            .iter()
+            .filter(|(_, account)| is_sbf_loader(account.owner()))
+            .map(|(program_id, _)| *program_id)
+            .collect()
     }
 
-    fn with_sysvars(mut self) -> Self {
+    fn with_sysvars(mut self, seed: Option<u64>) -> Self {
+        let unix_timestamp = match seed {
+            // a timestamp within the year after the start of 2024
+            Some(seed) => {
+                let hash = derive_from_seed(seed, b"timestamp").to_bytes();
+                let offset = u64::from_le_bytes(hash[..8].try_into().unwrap()) % SECONDS_PER_YEAR;
+                SEEDED_TIMESTAMP_START + offset as i64
+            }
+            None => get_current_timestamp() as i64,
+        };
         let clock = Clock {
-            unix_timestamp: get_current_timestamp() as i64,
+            unix_timestamp,
             ..Default::default()
         };
         self.set_sysvar(&clock);
@@ -165,7 +426,10 @@ impl TridentSVM {
         #[allow(deprecated)]
         let fees = Fees::default();
         self.set_sysvar(&fees);
-        let latest_blockhash = Hash::default();
+        let latest_blockhash = match seed {
+            Some(seed) => derive_from_seed(seed, b"blockhash"),
+            None => Hash::default(),
+        };
         #[allow(deprecated)]
         self.set_sysvar(&RecentBlockhashes::from_iter([IterItem(
             0,
@@ -186,81 +450,296 @@ impl TridentSVM {
                 &utils::create_loadable_account_for_test(builtint.name),
             );
 
-            self.processor.add_builtin(
-                &self,
+            self.add_builtin(
                 builtint.program_id,
-                builtint.name,
                 ProgramCacheEntry::new_builtin(0, builtint.name.len(), builtint.entrypoint),
             );
         });
 
         self
     }
-    fn with_solana_program_library(mut self) -> Self {
-        let spl_token = TridentProgram::new(
-            pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
-            None,
-            include_bytes!("solana-program-library/spl-token-mainnet.so").to_vec(),
-        );
+    /// Deploys the given bundled programs, programs which are not embedded are skipped.
+    pub(crate) fn with_solana_program_library(mut self, programs: &[BundledProgram]) -> Self {
+        for program in programs {
+            if let Some(elf) = program.elf() {
+                self.deploy_binary_program(
+                    &TridentProgram::new(program.program_id(), None, elf.to_vec())
+                        .with_loader(program.loader()),
+                );
+            }
+        }
 
-        self.deploy_binary_program(&spl_token);
+        self
+    }
 
-        // SPL Token 2022 added for new Token 2022 Trident features
-        let spl_token_2022 = TridentProgram::new(
-            pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"),
-            None,
-            include_bytes!("solana-program-library/spl-2022-token-mainnet.so").to_vec(),
-        );
+    pub fn clear_accounts(&mut self) {
+        self.accounts.reset_temp();
+    }
+}
 
-        self.deploy_binary_program(&spl_token_2022);
+/// Derives a value of the given domain from the seed.
+pub(crate) fn derive_from_seed(seed: u64, domain: &[u8]) -> Hash {
+    solana_sha256_hasher::hashv(&[domain, &seed.to_le_bytes()])
+}
 
-        let associated_token_program = TridentProgram::new(
-            pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"),
-            None,
-            include_bytes!("solana-program-library/associated-token-program-mainnet.so").to_vec(),
-        );
+fn is_sbf_loader(program_id: &Pubkey) -> bool {
+    solana_sdk_ids::bpf_loader::check_id(program_id)
+        || solana_sdk_ids::bpf_loader_deprecated::check_id(program_id)
+        || solana_sdk_ids::bpf_loader_upgradeable::check_id(program_id)
+        || solana_sdk_ids::loader_v4::check_id(program_id)
+}
 
-        self.deploy_binary_program(&associated_token_program);
+#[cfg(all(test, feature = "spl-token"))]
+mod tests {
+    use solana_svm::transaction_processing_result::TransactionProcessingResultExtensions;
+    use std::sync::Arc;
+
+    use solana_program_runtime::loaded_programs::ProgramCacheEntry;
+    use solana_program_runtime::loaded_programs::ProgramCacheEntryType;
+    use solana_pubkey::Pubkey;
+
+    use super::TridentSVM;
+    use crate::test_utils::initialize_mint_transaction;
+    use crate::types::bundled_program::BundledProgram;
+    use crate::types::execution_mode::ExecutionMode;
+    use crate::types::transaction_result::TridentTransactionProcessingResult;
+
+    fn compiled_programs(svm: &TridentSVM, program_id: &Pubkey) -> Vec<Arc<ProgramCacheEntry>> {
+        svm.processor
+            .program_cache
+            .read()
+            .unwrap()
+            .get_flattened_entries_for_tests()
+            .into_iter()
+            .filter(|(key, _)| key == program_id)
+            .map(|(_, entry)| entry)
+            .collect()
+    }
 
-        let metaplex_token_metadata = TridentProgram::new(
-            pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"),
-            None,
-            include_bytes!("solana-program-library/metaplex-token-metadata.so").to_vec(),
-        );
+    /// Initializes a new SPL Token mint with 6 decimals and no freeze authority.
+    fn initialize_mint(svm: &mut TridentSVM) -> (Pubkey, TridentTransactionProcessingResult) {
+        let (mint, transaction) =
+            initialize_mint_transaction(svm, &BundledProgram::SplToken.program_id());
+        (mint, svm.process_transaction_with_settle(transaction))
+    }
 
-        self.deploy_binary_program(&metaplex_token_metadata);
+    #[test]
+    fn test_instances_share_compiled_programs() {
+        let token_program = BundledProgram::SplToken.program_id();
+        let first = TridentSVM::default();
+        let second = TridentSVM::default();
+
+        // bundled programs are compiled on deployment, once per process
+        let first_entries = compiled_programs(&first, &token_program);
+        let second_entries = compiled_programs(&second, &token_program);
+        assert_eq!(first_entries.len(), 1);
+        assert_eq!(second_entries.len(), 1);
+        assert!(matches!(
+            first_entries[0].program,
+            ProgramCacheEntryType::Loaded(_)
+        ));
+        assert!(Arc::ptr_eq(&first_entries[0], &second_entries[0]));
+
+        // instrumented instances use their own environment
+        let profiled = TridentSVM::builder().with_compute_unit_profiling().build();
+        let profiled_entries = compiled_programs(&profiled, &token_program);
+        assert_eq!(profiled_entries.len(), 1);
+        assert!(!Arc::ptr_eq(&first_entries[0], &profiled_entries[0]));
+    }
 
-        // Interesting to have an Oracle program for testing programs with Price feed manipulation
-        // Another good program would be Pyth Oracle, which is good for cross-chain price feeds
-        let chainlink_oracle = TridentProgram::new(
-            pubkey!("HEvSKofvBgfaexv23kMabbYqxasxU3mQ4ibBMEmJWHny"),
-            None,
-            include_bytes!("solana-program-library/chainlink-oracle.so").to_vec(),
+    #[test]
+    fn test_execution_modes() {
+        let token_program = BundledProgram::SplToken.program_id();
+        let mut svm = TridentSVM::builder()
+            .with_execution_mode(ExecutionMode::Interpreter)
+            .build();
+
+        let (_, result) = initialize_mint(&mut svm);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+        assert_eq!(
+            result.get_execution_mode(&token_program),
+            Some(ExecutionMode::Interpreter)
+        );
+        let interpreted = compiled_programs(&svm, &token_program);
+        assert_eq!(interpreted.len(), 1);
+
+        svm.set_execution_mode(ExecutionMode::Jit);
+        let (_, result) = initialize_mint(&mut svm);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+        assert_eq!(
+            result.get_execution_mode(&token_program),
+            Some(ExecutionMode::Jit.resolve())
         );
+        let compiled = compiled_programs(&svm, &token_program);
+        assert_eq!(compiled.len(), 1);
 
-        self.deploy_binary_program(&chainlink_oracle);
+        #[cfg(all(not(target_os = "windows"), target_arch = "x86_64"))]
+        {
+            let is_jit_compiled = |entry: &ProgramCacheEntry| match &entry.program {
+                ProgramCacheEntryType::Loaded(executable) => {
+                    executable.get_compiled_program().is_some()
+                }
+                _ => false,
+            };
+            assert!(!is_jit_compiled(&interpreted[0]));
+            assert!(is_jit_compiled(&compiled[0]));
+        }
+    }
 
-        // Native Stake Pool (SPL Stake Pool):
-        // This program is used for managing stake pools, which can be useful for testing programs that interact with staking
-        let spl_stake_pool = TridentProgram::new(
-            pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy"),
-            None,
-            include_bytes!("solana-program-library/spl-stake-pool.so").to_vec(),
-        );
-        self.deploy_binary_program(&spl_stake_pool);
+    #[test]
+    fn test_workers_share_program_cache() {
+        let svm = TridentSVM::default();
+        let token_program = BundledProgram::SplToken.program_id();
+
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let mut worker = svm.new_worker();
+                std::thread::spawn(move || {
+                    let (mint, result) = initialize_mint(&mut worker);
+                    assert!(result.get_result().processing_results[0]
+                        .flattened_result()
+                        .is_ok());
+                    mint
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            let mint = worker.join().unwrap();
+            // accounts of the workers are separate
+            assert!(svm.get_account(&mint).is_none());
+        }
+
+        // the program compiled by the workers is in the cache of the original instance
+        let cache = svm.processor.program_cache.read().unwrap();
+        let entries: Vec<_> = cache
+            .get_flattened_entries_for_tests()
+            .into_iter()
+            .filter(|(program_id, _)| program_id == &token_program)
+            .collect();
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            entries[0].1.program,
+            ProgramCacheEntryType::Loaded(_)
+        ));
+    }
 
-        // Could be interesting to have candy machine for testing programs that interact with it Minting NFTs
-        let metaplex_candy_machine_v3 = TridentProgram::new(
-            pubkey!("CndyV3LdqHUfDLmE5naZjVN8rBZz4tqhdefbAnjHG3JR"),
-            None,
-            include_bytes!("solana-program-library/metaplex-candy-machine-v3.so").to_vec(),
+    #[test]
+    fn test_workers_keep_their_execution_mode() {
+        let token_program = BundledProgram::SplToken.program_id();
+        let mut svm = TridentSVM::builder()
+            .with_execution_mode(ExecutionMode::Jit)
+            .build();
+        let mut worker = svm.new_worker();
+
+        // the instance gets its own cache, the cache of the worker is not changed
+        svm.set_execution_mode(ExecutionMode::Interpreter);
+        assert!(!Arc::ptr_eq(
+            &svm.processor.program_cache,
+            &worker.processor.program_cache
+        ));
+
+        let (_, result) = initialize_mint(&mut svm);
+        assert_eq!(
+            result.get_execution_mode(&token_program),
+            Some(ExecutionMode::Interpreter)
+        );
+        let (_, result) = initialize_mint(&mut worker);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+        assert_eq!(
+            result.get_execution_mode(&token_program),
+            Some(ExecutionMode::Jit.resolve())
         );
-        self.deploy_binary_program(&metaplex_candy_machine_v3);
+    }
 
-        self
+    #[test]
+    fn test_fork_reuses_compiled_programs() {
+        let token_program = BundledProgram::SplToken.program_id();
+        let svm = TridentSVM::default();
+        let mut fork = svm.fork();
+        assert!(!Arc::ptr_eq(
+            &svm.processor.program_cache,
+            &fork.processor.program_cache
+        ));
+
+        let entries = compiled_programs(&svm, &token_program);
+        let forked_entries = compiled_programs(&fork, &token_program);
+        assert_eq!(forked_entries.len(), 1);
+        assert!(Arc::ptr_eq(&entries[0], &forked_entries[0]));
+
+        let (mint, result) = initialize_mint(&mut fork);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+        assert!(svm.get_account(&mint).is_none());
     }
 
-    pub fn clear_accounts(&mut self) {
-        self.accounts.reset_temp();
+    #[test]
+    #[cfg(feature = "spl-associated-token-account")]
+    fn test_execution_modes_of_cpi_targets() {
+        use solana_hash::Hash;
+        use solana_instruction::AccountMeta;
+        use solana_instruction::Instruction;
+        use solana_signer::Signer;
+        use solana_transaction::Transaction;
+
+        use crate::types::token_state::TokenAccount;
+
+        let token_program = BundledProgram::SplToken.program_id();
+        let associated_token_program = BundledProgram::AssociatedToken.program_id();
+        let mut svm = TridentSVM::builder()
+            .with_execution_mode(ExecutionMode::Interpreter)
+            .build();
+        let (mint, result) = initialize_mint(&mut svm);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+
+        // the associated token program creates the token account through CPI
+        let payer = svm.get_payer();
+        let wallet = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(
+            associated_token_program,
+            &[0],
+            vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(
+                    TokenAccount::associated_address(&wallet, &mint, &token_program),
+                    false,
+                ),
+                AccountMeta::new_readonly(wallet, false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new_readonly(solana_sdk_ids::system_program::id(), false),
+                AccountMeta::new_readonly(token_program, false),
+            ],
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        let result = svm.process_transaction_with_settle(transaction);
+        assert!(result.get_result().processing_results[0]
+            .flattened_result()
+            .is_ok());
+        assert_eq!(
+            result.get_execution_mode(&associated_token_program),
+            Some(ExecutionMode::Interpreter)
+        );
+        assert_eq!(
+            result.get_execution_mode(&token_program),
+            Some(ExecutionMode::Interpreter)
+        );
+        assert_eq!(
+            result.get_execution_mode(&solana_sdk_ids::system_program::id()),
+            None
+        );
     }
 }
@@ -0,0 +1,70 @@
+use solana_pubkey::Pubkey;
+
+/// Reads little endian fields of account data, every read returns `None` past the end.
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.bytes(N)?.try_into().ok()
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn pubkey(&mut self) -> Option<Pubkey> {
+        self.array().map(Pubkey::new_from_array)
+    }
+
+    /// Borsh string, trailing zeros used as padding are trimmed.
+    pub(crate) fn string(&mut self) -> Option<String> {
+        let len = u32::from_le_bytes(self.array()?);
+        let bytes = self.bytes(len as usize)?;
+        let string = String::from_utf8(bytes.to_vec()).ok()?;
+        Some(string.trim_end_matches('\0').to_string())
+    }
+
+    /// Borsh option, a missing tag at the end of the data is read as `None`.
+    pub(crate) fn option<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Option<T>,
+    ) -> Option<Option<T>> {
+        match self.0.first() {
+            None | Some(0) => {
+                self.bytes(1);
+                Some(None)
+            }
+            Some(1) => {
+                self.bytes(1);
+                read(self).map(Some)
+            }
+            Some(_) => None,
+        }
+    }
+
+    /// `COption<Pubkey>` of the SPL programs, tagged by four bytes.
+    pub(crate) fn coption_pubkey(&mut self) -> Option<Option<Pubkey>> {
+        let tag = u32::from_le_bytes(self.array()?);
+        let pubkey = self.pubkey()?;
+        Some((tag != 0).then_some(pubkey))
+    }
+
+    /// `OptionalNonZeroPubkey` of Token 2022, which stores `None` as the default pubkey.
+    pub(crate) fn optional_pubkey(&mut self) -> Option<Option<Pubkey>> {
+        let pubkey = self.pubkey()?;
+        Some((pubkey != Pubkey::default()).then_some(pubkey))
+    }
+}
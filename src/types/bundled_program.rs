@@ -0,0 +1,188 @@
+use solana_pubkey::pubkey;
+use solana_pubkey::Pubkey;
+
+use crate::types::trident_program::ProgramLoader;
+
+/// Embeds the ELF only if the cargo feature of the program is enabled.
+macro_rules! embedded_elf {
+    ($feature:literal, $path:literal) => {{
+        #[cfg(feature = $feature)]
+        let elf: Option<&'static [u8]> = Some(include_bytes!($path));
+        #[cfg(not(feature = $feature))]
+        let elf: Option<&'static [u8]> = None;
+        elf
+    }};
+}
+
+/// Mainnet programs bundled with Trident SVM.
+///
+/// Every program is embedded behind its own cargo feature, all of them are enabled by the
+/// default `program-library` feature. [`TridentSVM::default`](crate::trident_svm::TridentSVM)
+/// deploys all embedded programs, the builder can select a subset of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BundledProgram {
+    SplToken,
+    /// SPL Token 2022 added for new Token 2022 Trident features
+    SplToken2022,
+    AssociatedToken,
+    TokenMetadata,
+    /// Interesting to have an Oracle program for testing programs with Price feed manipulation
+    ChainlinkOracle,
+    /// Used for managing stake pools, which can be useful for testing programs that interact with staking
+    StakePool,
+    /// Interesting for testing programs that interact with Candy Machine Minting NFTs
+    CandyMachineV3,
+    /// SPL Memo v3, deployed through `bpf_loader` as on mainnet
+    Memo,
+    /// Core BPF Address Lookup Table program, replaced the builtin version
+    AddressLookupTable,
+}
+
+impl BundledProgram {
+    pub const ALL: [BundledProgram; 9] = [
+        BundledProgram::SplToken,
+        BundledProgram::SplToken2022,
+        BundledProgram::AssociatedToken,
+        BundledProgram::TokenMetadata,
+        BundledProgram::ChainlinkOracle,
+        BundledProgram::StakePool,
+        BundledProgram::CandyMachineV3,
+        BundledProgram::Memo,
+        BundledProgram::AddressLookupTable,
+    ];
+
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            BundledProgram::SplToken => pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+            BundledProgram::SplToken2022 => pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"),
+            BundledProgram::AssociatedToken => {
+                pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL")
+            }
+            BundledProgram::TokenMetadata => pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"),
+            BundledProgram::ChainlinkOracle => {
+                pubkey!("HEvSKofvBgfaexv23kMabbYqxasxU3mQ4ibBMEmJWHny")
+            }
+            BundledProgram::StakePool => pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy"),
+            BundledProgram::CandyMachineV3 => {
+                pubkey!("CndyV3LdqHUfDLmE5naZjVN8rBZz4tqhdefbAnjHG3JR")
+            }
+            BundledProgram::Memo => pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
+            BundledProgram::AddressLookupTable => solana_sdk_ids::address_lookup_table::id(),
+        }
+    }
+
+    /// Loader which owns the program on mainnet.
+    pub fn loader(&self) -> ProgramLoader {
+        match self {
+            BundledProgram::Memo => ProgramLoader::NonUpgradeable,
+            _ => ProgramLoader::Upgradeable,
+        }
+    }
+
+    /// Cargo feature which embeds the program.
+    pub fn feature(&self) -> &'static str {
+        match self {
+            BundledProgram::SplToken => "spl-token",
+            BundledProgram::SplToken2022 => "spl-token-2022",
+            BundledProgram::AssociatedToken => "spl-associated-token-account",
+            BundledProgram::TokenMetadata => "mpl-token-metadata",
+            BundledProgram::ChainlinkOracle => "chainlink-oracle",
+            BundledProgram::StakePool => "spl-stake-pool",
+            BundledProgram::CandyMachineV3 => "mpl-candy-machine-v3",
+            BundledProgram::Memo => "spl-memo",
+            BundledProgram::AddressLookupTable => "address-lookup-table",
+        }
+    }
+
+    /// ELF of the program, `None` if the feature of the program is disabled.
+    pub fn elf(&self) -> Option<&'static [u8]> {
+        match self {
+            BundledProgram::SplToken => {
+                embedded_elf!(
+                    "spl-token",
+                    "../solana-program-library/spl-token-mainnet.so"
+                )
+            }
+            BundledProgram::SplToken2022 => embedded_elf!(
+                "spl-token-2022",
+                "../solana-program-library/spl-2022-token-mainnet.so"
+            ),
+            BundledProgram::AssociatedToken => embedded_elf!(
+                "spl-associated-token-account",
+                "../solana-program-library/associated-token-program-mainnet.so"
+            ),
+            BundledProgram::TokenMetadata => embedded_elf!(
+                "mpl-token-metadata",
+                "../solana-program-library/metaplex-token-metadata.so"
+            ),
+            BundledProgram::ChainlinkOracle => embedded_elf!(
+                "chainlink-oracle",
+                "../solana-program-library/chainlink-oracle.so"
+            ),
+            BundledProgram::StakePool => {
+                embedded_elf!(
+                    "spl-stake-pool",
+                    "../solana-program-library/spl-stake-pool.so"
+                )
+            }
+            BundledProgram::CandyMachineV3 => embedded_elf!(
+                "mpl-candy-machine-v3",
+                "../solana-program-library/metaplex-candy-machine-v3.so"
+            ),
+            BundledProgram::Memo => {
+                embedded_elf!("spl-memo", "../solana-program-library/spl-memo.so")
+            }
+            BundledProgram::AddressLookupTable => embedded_elf!(
+                "address-lookup-table",
+                "../solana-program-library/address-lookup-table.so"
+            ),
+        }
+    }
+
+    /// Bundled programs whose features are enabled.
+    pub fn embedded() -> impl Iterator<Item = BundledProgram> {
+        Self::ALL
+            .into_iter()
+            .filter(|program| program.elf().is_some())
+    }
+}
+
+#[cfg(all(test, feature = "spl-token", feature = "spl-token-2022"))]
+mod tests {
+    use super::BundledProgram;
+    use crate::trident_svm::TridentSVM;
+    use crate::types::trident_program::TridentProgram;
+
+    #[test]
+    fn test_bundled_program_selection() {
+        let svm = TridentSVM::builder()
+            .with_bundled_programs(vec![BundledProgram::SplToken])
+            .build();
+        assert!(svm
+            .get_account(&BundledProgram::SplToken.program_id())
+            .is_some());
+        assert!(svm
+            .get_account(&BundledProgram::SplToken2022.program_id())
+            .is_none());
+
+        let svm = TridentSVM::builder().without_bundled_programs().build();
+        assert!(BundledProgram::ALL
+            .iter()
+            .all(|program| svm.get_account(&program.program_id()).is_none()));
+
+        // Token 2022 deployed under the id of SPL Token replaces the bundled version
+        let token_2022 = BundledProgram::SplToken2022.elf().unwrap();
+        let svm = TridentSVM::builder()
+            .with_sbf_programs(vec![TridentProgram::new(
+                BundledProgram::SplToken.program_id(),
+                None,
+                token_2022.to_vec(),
+            )])
+            .build();
+        assert_eq!(
+            svm.get_program_elf(&BundledProgram::SplToken.program_id())
+                .unwrap(),
+            token_2022
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use solana_pubkey::Pubkey;
+
+/// Anchor discriminator of the `Transmissions` account.
+const DISCRIMINATOR: [u8; 8] = [96, 179, 69, 66, 128, 129, 73, 117];
+const HEADER_LEN: usize = 192;
+const TRANSMISSION_LEN: usize = 48;
+const VERSION: u8 = 2;
+
+/// Feed account of the Chainlink store program, as read by its `query` instruction.
+///
+/// Only the live ring buffer is stored, it holds the rounds from the oldest to the latest.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChainlinkFeed {
+    pub owner: Pubkey,
+    pub writer: Pubkey,
+    /// At most 32 bytes of UTF-8, e.g. `SOL / USD`
+    pub description: String,
+    pub decimals: u8,
+    pub rounds: Vec<ChainlinkRound>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChainlinkRound {
+    pub round_id: u32,
+    pub slot: u64,
+    pub timestamp: u32,
+    pub answer: i128,
+}
+
+impl ChainlinkFeed {
+    pub fn latest_round(&self) -> Option<&ChainlinkRound> {
+        self.rounds.last()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let live_length = self.rounds.len().max(1);
+        let mut data = Vec::with_capacity(8 + HEADER_LEN + live_length * TRANSMISSION_LEN);
+        data.extend_from_slice(&DISCRIMINATOR);
+        data.push(VERSION);
+        // state, 0 is normal and 1 is flagged
+        data.push(0);
+        data.extend_from_slice(self.owner.as_ref());
+        // proposed owner
+        data.extend_from_slice(Pubkey::default().as_ref());
+        data.extend_from_slice(self.writer.as_ref());
+        let mut description = [0u8; 32];
+        let len = self.description.len().min(32);
+        description[..len].copy_from_slice(&self.description.as_bytes()[..len]);
+        data.extend_from_slice(&description);
+        data.push(self.decimals);
+        // flagging threshold
+        data.extend_from_slice(&0u32.to_le_bytes());
+        let latest_round_id = self.latest_round().map_or(0, |round| round.round_id);
+        data.extend_from_slice(&latest_round_id.to_le_bytes());
+        // granularity
+        data.push(1);
+        data.extend_from_slice(&(live_length as u32).to_le_bytes());
+        // live cursor points to the slot of the next round, which is the oldest one
+        data.extend_from_slice(&((self.rounds.len() % live_length) as u32).to_le_bytes());
+        // historical cursor
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.resize(8 + HEADER_LEN, 0);
+
+        for round in &self.rounds {
+            data.extend_from_slice(&round.slot.to_le_bytes());
+            data.extend_from_slice(&round.timestamp.to_le_bytes());
+            data.extend_from_slice(&[0; 4]);
+            data.extend_from_slice(&round.answer.to_le_bytes());
+            data.extend_from_slice(&[0; 16]);
+        }
+        data.resize(8 + HEADER_LEN + live_length * TRANSMISSION_LEN, 0);
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let data = data.strip_prefix(&DISCRIMINATOR)?;
+        if data.len() < HEADER_LEN || data[0] != VERSION {
+            return None;
+        }
+        let u32_at = |offset: usize| -> Option<u32> {
+            Some(u32::from_le_bytes(
+                data.get(offset..offset + 4)?.try_into().ok()?,
+            ))
+        };
+        let description = &data[98..130];
+        let description_len = description.iter().position(|byte| *byte == 0).unwrap_or(32);
+        let latest_round_id = u32_at(135)?;
+        let live_length = u32_at(140)? as usize;
+        let live_cursor = u32_at(144)? as usize;
+
+        let transmissions = data.get(HEADER_LEN..HEADER_LEN + live_length * TRANSMISSION_LEN)?;
+        let rounds_len = (latest_round_id as usize).min(live_length);
+        let rounds = (0..rounds_len)
+            .map(|index| {
+                // the oldest stored round is the first one before the cursor
+                let position = (live_cursor + live_length - rounds_len + index) % live_length;
+                let transmission = &transmissions[position * TRANSMISSION_LEN..];
+                ChainlinkRound {
+                    round_id: latest_round_id - (rounds_len - 1 - index) as u32,
+                    slot: u64::from_le_bytes(transmission[..8].try_into().unwrap()),
+                    timestamp: u32::from_le_bytes(transmission[8..12].try_into().unwrap()),
+                    answer: i128::from_le_bytes(transmission[16..32].try_into().unwrap()),
+                }
+            })
+            .collect();
+
+        Some(Self {
+            owner: Pubkey::try_from(&data[2..34]).ok()?,
+            writer: Pubkey::try_from(&data[66..98]).ok()?,
+            description: String::from_utf8_lossy(&description[..description_len]).into_owned(),
+            decimals: data[130],
+            rounds,
+        })
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+use solana_pubkey::Pubkey;
+
+/// Compute units consumed by executed programs, accumulated over all processed transactions.
+///
+/// Native programs deployed through [`crate::types::trident_entrypoint::TridentEntrypoint`] are
+/// profiled per invocation only, SBF programs are additionally profiled per syscall and, if the
+/// ELF contains a symbol table, per function.
+#[derive(Default, Clone, Debug)]
+pub struct ComputeUnitProfile {
+    programs: HashMap<Pubkey, ProgramComputeUnits>,
+    cpis: HashMap<(Pubkey, Pubkey), CallComputeUnits>,
+    syscalls: HashMap<String, CallComputeUnits>,
+    functions: HashMap<Pubkey, HashMap<String, u64>>,
+    folded_stacks: HashMap<String, u64>,
+}
+
+/// Compute units consumed by one program.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgramComputeUnits {
+    pub invocations: u64,
+    /// Compute units including the programs invoked through CPI.
+    pub units: u64,
+    /// Compute units excluding the programs invoked through CPI.
+    pub self_units: u64,
+}
+
+/// Compute units consumed by a cross program invocation or a syscall.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallComputeUnits {
+    pub calls: u64,
+    pub units: u64,
+}
+
+impl CallComputeUnits {
+    fn record(&mut self, units: u64) {
+        self.calls = self.calls.saturating_add(1);
+        self.units = self.units.saturating_add(units);
+    }
+}
+
+impl ComputeUnitProfile {
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+
+    pub fn program(&self, program_id: &Pubkey) -> Option<&ProgramComputeUnits> {
+        self.programs.get(program_id)
+    }
+
+    pub fn programs(&self) -> impl Iterator<Item = (&Pubkey, &ProgramComputeUnits)> {
+        self.programs.iter()
+    }
+
+    /// Cross program invocations as `(caller, callee)` pairs, the units include
+    /// everything the callee invoked.
+    pub fn cpis(&self) -> impl Iterator<Item = (&(Pubkey, Pubkey), &CallComputeUnits)> {
+        self.cpis.iter()
+    }
+
+    /// Syscalls keyed by name, the units exclude programs invoked through the syscall.
+    pub fn syscalls(&self) -> impl Iterator<Item = (&str, &CallComputeUnits)> {
+        self.syscalls
+            .iter()
+            .map(|(name, units)| (name.as_str(), units))
+    }
+
+    /// Compute units of the instructions executed in every function of the program,
+    /// sorted from the most expensive function.
+    pub fn functions(&self, program_id: &Pubkey) -> Vec<(&str, u64)> {
+        let mut functions: Vec<_> = self
+            .functions
+            .get(program_id)
+            .into_iter()
+            .flatten()
+            .map(|(name, units)| (name.as_str(), *units))
+            .collect();
+        functions.sort_by(|(a_name, a_units), (b_name, b_units)| {
+            b_units.cmp(a_units).then(a_name.cmp(b_name))
+        });
+        functions
+    }
+
+    /// Call stacks separated by `;`, with the compute units consumed at the top of the stack.
+    pub fn folded_stacks(&self) -> BTreeMap<&str, u64> {
+        self.folded_stacks
+            .iter()
+            .map(|(stack, units)| (stack.as_str(), *units))
+            .collect()
+    }
+
+    /// Writes the folded stacks in the format consumed by `flamegraph.pl` and `inferno`.
+    pub fn write_folded_stacks<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        for (stack, units) in self.folded_stacks() {
+            writeln!(writer, "{stack} {units}")?;
+        }
+        Ok(())
+    }
+
+    pub fn save_folded_stacks<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_folded_stacks(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub(crate) fn record_invocation(&mut self, program_id: &Pubkey, units: u64, self_units: u64) {
+        let program = self.programs.entry(*program_id).or_default();
+        program.invocations = program.invocations.saturating_add(1);
+        program.units = program.units.saturating_add(units);
+        program.self_units = program.self_units.saturating_add(self_units);
+    }
+
+    pub(crate) fn record_cpi(&mut self, caller: &Pubkey, callee: &Pubkey, units: u64) {
+        self.cpis
+            .entry((*caller, *callee))
+            .or_default()
+            .record(units);
+    }
+
+    pub(crate) fn record_syscall(&mut self, name: &str, units: u64) {
+        match self.syscalls.get_mut(name) {
+            Some(syscall) => syscall.record(units),
+            None => self
+                .syscalls
+                .entry(name.to_string())
+                .or_default()
+                .record(units),
+        }
+    }
+
+    pub(crate) fn record_function(&mut self, program_id: &Pubkey, name: &str, units: u64) {
+        let functions = self.functions.entry(*program_id).or_default();
+        match functions.get_mut(name) {
+            Some(function_units) => *function_units = function_units.saturating_add(units),
+            None => {
+                functions.insert(name.to_string(), units);
+            }
+        }
+    }
+
+    pub(crate) fn record_stack(&mut self, frames: &[String], units: u64) {
+        if units == 0 {
+            return;
+        }
+        let stack_units = self.folded_stacks.entry(frames.join(";")).or_default();
+        *stack_units = stack_units.saturating_add(units);
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+use solana_pubkey::Pubkey;
+
+/// Line coverage report of SBF programs, built from [`crate::types::sbf_coverage::SbfCoverage`].
+#[derive(Default, Clone, Debug)]
+pub struct CoverageReport {
+    files: Vec<FileCoverage>,
+}
+
+/// What the lines of a [`FileCoverage`] refer to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoverageGranularity {
+    /// Source lines resolved through the DWARF information of the program.
+    SourceLines,
+    /// Instructions of the disassembled program, used when the ELF has no debug information.
+    Disassembly,
+}
+
+/// Coverage of one source file, or of the disassembly of one program.
+#[derive(Clone, Debug)]
+pub struct FileCoverage {
+    pub program_id: Pubkey,
+    pub path: String,
+    pub granularity: CoverageGranularity,
+    /// Execution count of every instrumented line, lines which were not executed have 0.
+    pub lines: BTreeMap<u32, u64>,
+    /// Text of the file if available, the first entry is line 1.
+    pub listing: Option<Vec<String>>,
+}
+
+impl FileCoverage {
+    pub fn lines_found(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|hits| **hits > 0).count()
+    }
+}
+
+impl CoverageReport {
+    pub(crate) fn add_file(&mut self, file: FileCoverage) {
+        self.files.push(file);
+    }
+
+    pub fn files(&self) -> &[FileCoverage] {
+        &self.files
+    }
+
+    pub fn lines_found(&self) -> usize {
+        self.files.iter().map(FileCoverage::lines_found).sum()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.files.iter().map(FileCoverage::lines_hit).sum()
+    }
+
+    /// Writes the report in the lcov tracefile format.
+    pub fn write_lcov<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        for file in &self.files {
+            writeln!(writer, "TN:{}", file.program_id)?;
+            writeln!(writer, "SF:{}", file.path)?;
+            for (line, hits) in &file.lines {
+                writeln!(writer, "DA:{line},{hits}")?;
+            }
+            writeln!(writer, "LF:{}", file.lines_found())?;
+            writeln!(writer, "LH:{}", file.lines_hit())?;
+            writeln!(writer, "end_of_record")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the report as a single self-contained HTML page.
+    pub fn write_html<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(writer, "<html><head><meta charset=\"utf-8\">")?;
+        writeln!(writer, "<title>Trident SVM coverage</title>")?;
+        writeln!(writer, "<style>{HTML_STYLE}</style>")?;
+        writeln!(writer, "</head><body>")?;
+        writeln!(
+            writer,
+            "<h1>SBF coverage</h1><p>{} of {} lines covered ({})</p>",
+            self.lines_hit(),
+            self.lines_found(),
+            percentage(self.lines_hit(), self.lines_found())
+        )?;
+
+        writeln!(writer, "<table class=\"summary\">")?;
+        writeln!(
+            writer,
+            "<tr><th>Program</th><th>File</th><th>Lines</th><th>Coverage</th></tr>"
+        )?;
+        for (index, file) in self.files.iter().enumerate() {
+            writeln!(
+                writer,
+                "<tr><td>{}</td><td><a href=\"#file-{index}\">{}</a></td><td>{} / {}</td><td>{}</td></tr>",
+                file.program_id,
+                escape_html(&file.path),
+                file.lines_hit(),
+                file.lines_found(),
+                percentage(file.lines_hit(), file.lines_found())
+            )?;
+        }
+        writeln!(writer, "</table>")?;
+
+        for (index, file) in self.files.iter().enumerate() {
+            writeln!(
+                writer,
+                "<h2 id=\"file-{index}\">{}</h2><p>Program {}</p>",
+                escape_html(&file.path),
+                file.program_id
+            )?;
+            writeln!(writer, "<table class=\"listing\">")?;
+            match &file.listing {
+                Some(listing) => {
+                    for (line_index, text) in listing.iter().enumerate() {
+                        let line = line_index as u32 + 1;
+                        write_html_line(writer, line, file.lines.get(&line), text)?;
+                    }
+                }
+                None => {
+                    for (line, hits) in &file.lines {
+                        write_html_line(writer, *line, Some(hits), "source not available")?;
+                    }
+                }
+            }
+            writeln!(writer, "</table>")?;
+        }
+
+        writeln!(writer, "</body></html>")
+    }
+
+    pub fn save_lcov<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_lcov(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn save_html<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_html(&mut writer)?;
+        writer.flush()
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+table{border-collapse:collapse}\
+.summary td,.summary th{border:1px solid #ccc;padding:2px 8px;text-align:left}\
+.listing{font-family:monospace;font-size:13px;width:100%}\
+.listing td{padding:0 6px;white-space:pre}\
+.ln,.cnt{color:#666;text-align:right}\
+.hit{background:#dfd}.miss{background:#fdd}";
+
+fn write_html_line<W: Write>(
+    writer: &mut W,
+    line: u32,
+    hits: Option<&u64>,
+    text: &str,
+) -> std::io::Result<()> {
+    let (class, count) = match hits {
+        Some(0) => ("miss", "0".to_string()),
+        Some(hits) => ("hit", hits.to_string()),
+        None => ("", String::new()),
+    };
+    writeln!(
+        writer,
+        "<tr class=\"{class}\"><td class=\"ln\">{line}</td><td class=\"cnt\">{count}</td><td>{}</td></tr>",
+        escape_html(text)
+    )
+}
+
+fn percentage(hit: usize, found: usize) -> String {
+    if found == 0 {
+        return "-".to_string();
+    }
+    format!("{:.1}%", hit as f64 * 100.0 / found as f64)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
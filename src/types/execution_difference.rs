@@ -0,0 +1,61 @@
+use solana_account::AccountSharedData;
+use solana_pubkey::Pubkey;
+use solana_transaction_error::TransactionError;
+
+use crate::types::transaction_result::TridentTransactionProcessingResult;
+
+/// Rule of an expected difference which is not reported, see
+/// [`crate::differential_runner::DifferentialRunner::allow_difference`].
+pub type DifferenceRule = dyn Fn(&ExecutionDifference) -> bool + Send + Sync;
+
+/// A difference between the executions of a transaction by the reference and the candidate
+/// instance of a [`crate::differential_runner::DifferentialRunner`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExecutionDifference {
+    Result {
+        reference: Result<(), TransactionError>,
+        candidate: Result<(), TransactionError>,
+    },
+    /// The logs without the compute units consumed by the programs.
+    Logs {
+        reference: Vec<String>,
+        candidate: Vec<String>,
+    },
+    /// The program which set the return data and the data.
+    ReturnData {
+        reference: Option<(Pubkey, Vec<u8>)>,
+        candidate: Option<(Pubkey, Vec<u8>)>,
+    },
+    /// A writable account of the transaction after its execution.
+    Account {
+        address: Pubkey,
+        reference: Option<AccountSharedData>,
+        candidate: Option<AccountSharedData>,
+    },
+}
+
+/// The executions of a transaction by both instances and their differences.
+pub struct DifferentialReport {
+    pub(crate) reference: TridentTransactionProcessingResult,
+    pub(crate) candidate: TridentTransactionProcessingResult,
+    pub(crate) differences: Vec<ExecutionDifference>,
+}
+
+impl DifferentialReport {
+    pub fn get_reference_result(&self) -> &TridentTransactionProcessingResult {
+        &self.reference
+    }
+
+    pub fn get_candidate_result(&self) -> &TridentTransactionProcessingResult {
+        &self.candidate
+    }
+
+    pub fn get_differences(&self) -> &[ExecutionDifference] {
+        &self.differences
+    }
+
+    /// Whether both instances executed the transaction the same way.
+    pub fn is_equivalent(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
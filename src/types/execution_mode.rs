@@ -0,0 +1,29 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// How SBF programs are executed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExecutionMode {
+    /// Instructions are interpreted one at a time, slower but the execution
+    /// can be traced precisely.
+    Interpreter,
+    /// Programs are compiled to native code before their first execution.
+    #[default]
+    Jit,
+}
+
+impl ExecutionMode {
+    /// Whether programs can be JIT compiled on this platform.
+    pub const fn is_jit_supported() -> bool {
+        cfg!(all(not(target_os = "windows"), target_arch = "x86_64"))
+    }
+
+    /// Returns the mode programs are executed in on this platform,
+    /// the interpreter if JIT compilation is not supported.
+    pub const fn resolve(self) -> Self {
+        match self {
+            ExecutionMode::Jit if !Self::is_jit_supported() => ExecutionMode::Interpreter,
+            mode => mode,
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+
+use serde::Deserialize;
+use serde::Serialize;
+use solana_instruction::error::InstructionError;
+use solana_pubkey::Pubkey;
+
+/// Faults injected into processed transactions, see [`crate::trident_svm::TridentSVM::set_fault_injection`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FaultInjection {
+    pub(crate) missing_accounts: HashSet<Pubkey>,
+    pub(crate) max_instruction_stack_depth: Option<usize>,
+    pub(crate) compute_unit_limit: Option<u64>,
+    pub(crate) syscall_faults: Vec<SyscallFault>,
+}
+
+/// A failing call of a syscall.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyscallFault {
+    /// Name the syscall is registered under, such as `sol_invoke_signed_rust`.
+    pub name: String,
+    /// Index of the failing call within the transaction, counted from `0`.
+    pub call: u64,
+    /// Error the program fails with.
+    pub error: InstructionError,
+}
+
+impl FaultInjection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads of the account find no account. A missing fee payer fails the transaction
+    /// with `AccountNotFound`, other accounts are loaded as new empty accounts and
+    /// missing programs fail it with `ProgramAccountNotFound`.
+    pub fn with_missing_account(mut self, address: Pubkey) -> Self {
+        self.missing_accounts.insert(address);
+        self
+    }
+
+    /// Limits the invocation stack, `1` allows top level instructions only. Deeper
+    /// invocations fail with `CallDepth`, the default limit is `5`.
+    pub fn with_max_instruction_stack_depth(mut self, depth: usize) -> Self {
+        self.max_instruction_stack_depth = Some(depth);
+        self
+    }
+
+    /// Limits the compute units of the transaction. Every executed SBF instruction
+    /// consumes one unit, so the limit selects the instruction at which the program
+    /// runs out of compute units and fails with `ProgramFailedToComplete`.
+    pub fn with_compute_unit_limit(mut self, units: u64) -> Self {
+        self.compute_unit_limit = Some(units);
+        self
+    }
+
+    /// Fails the given call of the syscall with the error, instead of executing it.
+    pub fn with_syscall_fault(mut self, name: &str, call: u64, error: InstructionError) -> Self {
+        self.syscall_faults.push(SyscallFault {
+            name: name.to_string(),
+            call,
+            error,
+        });
+        self
+    }
+
+    pub fn is_missing_account(&self, address: &Pubkey) -> bool {
+        self.missing_accounts.contains(address)
+    }
+}
@@ -1,3 +1,18 @@
+pub(crate) mod account_reader;
+pub mod bundled_program;
+pub mod chainlink_feed;
+pub mod compute_unit_profile;
+pub mod coverage_report;
+pub mod execution_difference;
+pub mod execution_mode;
+pub mod fault_injection;
+pub mod name_record;
+pub mod nft_metadata;
+pub mod program_mock;
+pub mod pyth_price_update;
+pub mod sbf_coverage;
+pub mod token_state;
+pub mod transaction_recording;
 pub mod transaction_result;
 pub mod trident_account;
 #[cfg(feature = "syscall-v2")]
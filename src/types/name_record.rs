@@ -0,0 +1,58 @@
+use solana_pubkey::pubkey;
+use solana_pubkey::Pubkey;
+
+pub const NAME_SERVICE_PROGRAM_ID: Pubkey = pubkey!("namesLPneVptA9Z5rqUDD9tMTWEJwofgaYwp8cawRkX");
+
+/// Prefix hashed together with the name to derive the record address.
+const HASH_PREFIX: &str = "SPL Name Service";
+const HEADER_LEN: usize = 96;
+
+/// Record of the SPL Name Service, the header is followed by arbitrary data.
+///
+/// The Name Service program itself is not bundled, consumers read the record directly.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NameRecord {
+    pub parent_name: Pubkey,
+    pub owner: Pubkey,
+    pub class: Pubkey,
+    pub data: Vec<u8>,
+}
+
+impl NameRecord {
+    /// Address of the record as derived by the Name Service program.
+    pub fn address(name: &str, class: Option<&Pubkey>, parent_name: Option<&Pubkey>) -> Pubkey {
+        let hashed_name = solana_sha256_hasher::hashv(&[HASH_PREFIX.as_bytes(), name.as_bytes()]);
+        let default = Pubkey::default();
+        Pubkey::find_program_address(
+            &[
+                hashed_name.as_ref(),
+                class.unwrap_or(&default).as_ref(),
+                parent_name.unwrap_or(&default).as_ref(),
+            ],
+            &NAME_SERVICE_PROGRAM_ID,
+        )
+        .0
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(HEADER_LEN + self.data.len());
+        data.extend_from_slice(self.parent_name.as_ref());
+        data.extend_from_slice(self.owner.as_ref());
+        data.extend_from_slice(self.class.as_ref());
+        data.extend_from_slice(&self.data);
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LEN {
+            return None;
+        }
+        let pubkey_at = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]).ok();
+        Some(Self {
+            parent_name: pubkey_at(0)?,
+            owner: pubkey_at(32)?,
+            class: pubkey_at(64)?,
+            data: data[HEADER_LEN..].to_vec(),
+        })
+    }
+}
@@ -0,0 +1,293 @@
+use solana_pubkey::Pubkey;
+
+use crate::types::account_reader::Reader;
+use crate::types::bundled_program::BundledProgram;
+
+const METADATA_LEN: usize = 679;
+const MASTER_EDITION_LEN: usize = 282;
+const TOKEN_RECORD_LEN: usize = 80;
+const MAX_NAME_LEN: usize = 32;
+const MAX_SYMBOL_LEN: usize = 10;
+const MAX_URI_LEN: usize = 200;
+
+const KEY_METADATA: u8 = 4;
+const KEY_MASTER_EDITION: u8 = 6;
+const KEY_TOKEN_RECORD: u8 = 11;
+
+/// Metadata account of the Metaplex Token Metadata program.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NftMetadata {
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<NftCreator>>,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub edition_nonce: Option<u8>,
+    pub token_standard: Option<TokenStandard>,
+    pub collection: Option<NftCollection>,
+    /// Size of a sized collection, set only on the metadata of the collection NFT.
+    pub collection_size: Option<u64>,
+    /// Authorization rule set of a programmable NFT.
+    pub rule_set: Option<Pubkey>,
+}
+
+/// Accounts of an NFT minted by [`TridentSVM::mint_nft`](crate::trident_svm::TridentSVM::mint_nft).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MintedNft {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub metadata: Pubkey,
+    pub master_edition: Pubkey,
+    /// Token record of a programmable NFT
+    pub token_record: Option<Pubkey>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NftCreator {
+    pub address: Pubkey,
+    pub verified: bool,
+    /// Share in percent, the shares of all creators add up to 100
+    pub share: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NftCollection {
+    pub verified: bool,
+    /// Mint of the collection NFT
+    pub key: Pubkey,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenStandard {
+    NonFungible,
+    FungibleAsset,
+    Fungible,
+    NonFungibleEdition,
+    ProgrammableNonFungible,
+    ProgrammableNonFungibleEdition,
+}
+
+impl TokenStandard {
+    pub fn is_programmable(&self) -> bool {
+        matches!(
+            self,
+            TokenStandard::ProgrammableNonFungible | TokenStandard::ProgrammableNonFungibleEdition
+        )
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        let token_standard = match value {
+            0 => TokenStandard::NonFungible,
+            1 => TokenStandard::FungibleAsset,
+            2 => TokenStandard::Fungible,
+            3 => TokenStandard::NonFungibleEdition,
+            4 => TokenStandard::ProgrammableNonFungible,
+            5 => TokenStandard::ProgrammableNonFungibleEdition,
+            _ => return None,
+        };
+        Some(token_standard)
+    }
+}
+
+impl NftMetadata {
+    pub fn address(mint: &Pubkey) -> Pubkey {
+        let program_id = BundledProgram::TokenMetadata.program_id();
+        Pubkey::find_program_address(
+            &[b"metadata", program_id.as_ref(), mint.as_ref()],
+            &program_id,
+        )
+        .0
+    }
+
+    pub fn master_edition_address(mint: &Pubkey) -> (Pubkey, u8) {
+        let program_id = BundledProgram::TokenMetadata.program_id();
+        Pubkey::find_program_address(
+            &[b"metadata", program_id.as_ref(), mint.as_ref(), b"edition"],
+            &program_id,
+        )
+    }
+
+    /// Token record of a programmable NFT held in the token account.
+    pub fn token_record_address(mint: &Pubkey, token_account: &Pubkey) -> (Pubkey, u8) {
+        let program_id = BundledProgram::TokenMetadata.program_id();
+        Pubkey::find_program_address(
+            &[
+                b"metadata",
+                program_id.as_ref(),
+                mint.as_ref(),
+                b"token_record",
+                token_account.as_ref(),
+            ],
+            &program_id,
+        )
+    }
+
+    /// Serializes the metadata with the strings padded to their maximal length, as the
+    /// Token Metadata program stores them.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(METADATA_LEN);
+        data.push(KEY_METADATA);
+        data.extend_from_slice(self.update_authority.as_ref());
+        data.extend_from_slice(self.mint.as_ref());
+        push_string(&mut data, &self.name, MAX_NAME_LEN);
+        push_string(&mut data, &self.symbol, MAX_SYMBOL_LEN);
+        push_string(&mut data, &self.uri, MAX_URI_LEN);
+        data.extend_from_slice(&self.seller_fee_basis_points.to_le_bytes());
+        push_option(&mut data, self.creators.as_ref(), |data, creators| {
+            data.extend_from_slice(&(creators.len() as u32).to_le_bytes());
+            for creator in creators {
+                data.extend_from_slice(creator.address.as_ref());
+                data.push(creator.verified as u8);
+                data.push(creator.share);
+            }
+        });
+        data.push(self.primary_sale_happened as u8);
+        data.push(self.is_mutable as u8);
+        push_option(&mut data, self.edition_nonce.as_ref(), |data, nonce| {
+            data.push(*nonce)
+        });
+        push_option(&mut data, self.token_standard.as_ref(), |data, standard| {
+            data.push(*standard as u8)
+        });
+        push_option(&mut data, self.collection.as_ref(), |data, collection| {
+            data.push(collection.verified as u8);
+            data.extend_from_slice(collection.key.as_ref());
+        });
+        // uses
+        data.push(0);
+        // collection details V1
+        push_option(&mut data, self.collection_size.as_ref(), |data, size| {
+            data.push(0);
+            data.extend_from_slice(&size.to_le_bytes());
+        });
+        // programmable config V1
+        let programmable = self
+            .token_standard
+            .is_some_and(|standard| standard.is_programmable());
+        push_option(
+            &mut data,
+            programmable.then_some(&self.rule_set),
+            |data, rule_set| {
+                data.push(0);
+                push_option(data, rule_set.as_ref(), |data, rule_set| {
+                    data.extend_from_slice(rule_set.as_ref())
+                });
+            },
+        );
+        data.resize(METADATA_LEN.max(data.len()), 0);
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader(data);
+        if reader.u8()? != KEY_METADATA {
+            return None;
+        }
+        let update_authority = reader.pubkey()?;
+        let mint = reader.pubkey()?;
+        let name = reader.string()?;
+        let symbol = reader.string()?;
+        let uri = reader.string()?;
+        let seller_fee_basis_points = u16::from_le_bytes(reader.array()?);
+        let creators = reader.option(|reader| {
+            let len = u32::from_le_bytes(reader.array()?);
+            (0..len)
+                .map(|_| {
+                    Some(NftCreator {
+                        address: reader.pubkey()?,
+                        verified: reader.u8()? != 0,
+                        share: reader.u8()?,
+                    })
+                })
+                .collect()
+        })?;
+        let primary_sale_happened = reader.u8()? != 0;
+        let is_mutable = reader.u8()? != 0;
+        let edition_nonce = reader.option(Reader::u8)?;
+        let token_standard = reader.option(|reader| TokenStandard::from_u8(reader.u8()?))?;
+        let collection = reader.option(|reader| {
+            Some(NftCollection {
+                verified: reader.u8()? != 0,
+                key: reader.pubkey()?,
+            })
+        })?;
+        // Older metadata accounts end before the optional trailing fields
+        reader.option(|reader| reader.bytes(17))?;
+        let collection_size = reader
+            .option(|reader| {
+                let version = reader.u8()?;
+                let size = u64::from_le_bytes(reader.array()?);
+                (version == 0).then_some(size)
+            })
+            .flatten();
+        let rule_set = reader
+            .option(|reader| {
+                reader.u8()?;
+                reader.option(Reader::pubkey)
+            })
+            .flatten()
+            .flatten();
+
+        Some(Self {
+            update_authority,
+            mint,
+            name,
+            symbol,
+            uri,
+            seller_fee_basis_points,
+            creators,
+            primary_sale_happened,
+            is_mutable,
+            edition_nonce,
+            token_standard,
+            collection,
+            collection_size,
+            rule_set,
+        })
+    }
+}
+
+/// Serializes the master edition of an NFT without prints.
+pub(crate) fn serialize_master_edition(max_supply: Option<u64>) -> Vec<u8> {
+    let mut data = Vec::with_capacity(MASTER_EDITION_LEN);
+    data.push(KEY_MASTER_EDITION);
+    // supply
+    data.extend_from_slice(&0u64.to_le_bytes());
+    push_option(&mut data, max_supply.as_ref(), |data, max_supply| {
+        data.extend_from_slice(&max_supply.to_le_bytes())
+    });
+    data.resize(MASTER_EDITION_LEN, 0);
+    data
+}
+
+/// Serializes the unlocked token record without a delegate.
+pub(crate) fn serialize_token_record(bump: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(TOKEN_RECORD_LEN);
+    data.push(KEY_TOKEN_RECORD);
+    data.push(bump);
+    // token state unlocked
+    data.push(0);
+    data.resize(TOKEN_RECORD_LEN, 0);
+    data
+}
+
+fn push_string(data: &mut Vec<u8>, value: &str, len: usize) {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.resize(len.max(bytes.len()), 0);
+    data.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    data.extend_from_slice(&bytes);
+}
+
+fn push_option<T>(data: &mut Vec<u8>, value: Option<&T>, push: impl FnOnce(&mut Vec<u8>, &T)) {
+    match value {
+        Some(value) => {
+            data.push(1);
+            push(data, value);
+        }
+        None => data.push(0),
+    }
+}
@@ -0,0 +1,45 @@
+use solana_account::AccountSharedData;
+use solana_instruction::error::InstructionError;
+use solana_pubkey::Pubkey;
+
+/// Closure which is executed instead of a mocked program, see [`crate::trident_svm::TridentSVM::mock_program`].
+pub type ProgramMock = dyn Fn(&mut MockInvocation) -> Result<(), InstructionError> + Send + Sync;
+
+/// An instruction account passed to a mocked program.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockAccount {
+    pub address: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub account: AccountSharedData,
+}
+
+/// An invocation of a mocked program, either by a top level instruction or through CPI.
+///
+/// Changes of the lamports, data or owner of the accounts are written back once the
+/// mock returns successfully, subject to the same checks as changes made by a program.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockInvocation {
+    pub program_id: Pubkey,
+    pub accounts: Vec<MockAccount>,
+    pub data: Vec<u8>,
+    /// Height of the invocation stack, `1` for top level instructions.
+    pub stack_height: usize,
+    /// Compute units consumed by the invocation, `1` unless changed by the mock.
+    pub compute_units: u64,
+    pub(crate) return_data: Option<Vec<u8>>,
+}
+
+impl MockInvocation {
+    /// Sets the return data seen by the caller, the same as `set_return_data` in a program.
+    pub fn set_return_data(&mut self, data: &[u8]) {
+        self.return_data = Some(data.to_vec());
+    }
+
+    /// Returns the instruction account with the given address.
+    pub fn account(&self, address: &Pubkey) -> Option<&MockAccount> {
+        self.accounts
+            .iter()
+            .find(|account| &account.address == address)
+    }
+}
@@ -0,0 +1,73 @@
+use solana_pubkey::pubkey;
+use solana_pubkey::Pubkey;
+
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+/// Anchor discriminator of the `PriceUpdateV2` account.
+const DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+/// Size of the account as allocated by the Pyth receiver.
+const PRICE_UPDATE_LEN: usize = 134;
+
+/// `PriceUpdateV2` account of the Pyth receiver program, as read by `pyth-solana-receiver-sdk`.
+///
+/// The price is always fully verified, partially verified updates are rejected by most consumers.
+/// The receiver program itself is not bundled, consumers read the account directly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PythPriceUpdate {
+    pub write_authority: Pubkey,
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+    pub posted_slot: u64,
+}
+
+impl PythPriceUpdate {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(PRICE_UPDATE_LEN);
+        data.extend_from_slice(&DISCRIMINATOR);
+        data.extend_from_slice(self.write_authority.as_ref());
+        // VerificationLevel::Full
+        data.push(1);
+        data.extend_from_slice(&self.feed_id);
+        data.extend_from_slice(&self.price.to_le_bytes());
+        data.extend_from_slice(&self.conf.to_le_bytes());
+        data.extend_from_slice(&self.exponent.to_le_bytes());
+        data.extend_from_slice(&self.publish_time.to_le_bytes());
+        data.extend_from_slice(&self.prev_publish_time.to_le_bytes());
+        data.extend_from_slice(&self.ema_price.to_le_bytes());
+        data.extend_from_slice(&self.ema_conf.to_le_bytes());
+        data.extend_from_slice(&self.posted_slot.to_le_bytes());
+        data.resize(PRICE_UPDATE_LEN, 0);
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let data = data.strip_prefix(&DISCRIMINATOR)?;
+        let write_authority = Pubkey::try_from(data.get(..32)?).ok()?;
+        // Partial verification stores the number of signatures
+        let data = match data.get(32)? {
+            0 => data.get(34..)?,
+            1 => data.get(33..)?,
+            _ => return None,
+        };
+        let bytes =
+            |offset: usize| -> Option<[u8; 8]> { data.get(offset..offset + 8)?.try_into().ok() };
+        Some(Self {
+            write_authority,
+            feed_id: data.get(..32)?.try_into().ok()?,
+            price: i64::from_le_bytes(bytes(32)?),
+            conf: u64::from_le_bytes(bytes(40)?),
+            exponent: i32::from_le_bytes(data.get(48..52)?.try_into().ok()?),
+            publish_time: i64::from_le_bytes(bytes(52)?),
+            prev_publish_time: i64::from_le_bytes(bytes(60)?),
+            ema_price: i64::from_le_bytes(bytes(68)?),
+            ema_conf: u64::from_le_bytes(bytes(76)?),
+            posted_slot: u64::from_le_bytes(bytes(84)?),
+        })
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+use solana_program_runtime::solana_sbpf::ebpf;
+use solana_pubkey::Pubkey;
+
+/// Edge source used for the first instruction executed by an invocation.
+pub const ENTRY_EDGE: u64 = u64::MAX;
+
+/// Coverage collected from SBF programs, keyed by program id.
+#[derive(Default, Clone, Debug)]
+pub struct SbfCoverage {
+    programs: HashMap<Pubkey, ProgramCoverage>,
+}
+
+/// Coverage of a single SBF program.
+///
+/// Program counters are instruction indexes into the `.text` section of the program.
+/// Edges are recorded for every transition out of a jump, including the fall through
+/// of a conditional jump, and for every transition which does not fall through to the
+/// next instruction, which makes their targets the basic block entries.
+#[derive(Default, Clone, Debug)]
+pub struct ProgramCoverage {
+    pc_hits: BTreeMap<u64, u64>,
+    edges: HashMap<(u64, u64), u64>,
+}
+
+/// Source line an SBF instruction was compiled from.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+}
+
+impl SbfCoverage {
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+
+    pub fn program(&self, program_id: &Pubkey) -> Option<&ProgramCoverage> {
+        self.programs.get(program_id)
+    }
+
+    pub fn programs(&self) -> impl Iterator<Item = (&Pubkey, &ProgramCoverage)> {
+        self.programs.iter()
+    }
+
+    /// Records the program counters of one program invocation, in execution order.
+    ///
+    /// `text` is the `.text` section of the program, it determines the jumps and the width
+    /// of the instructions. Instructions outside of it are one slot wide and never jump.
+    pub fn record(&mut self, program_id: &Pubkey, pcs: &[u64], text: &[u8]) {
+        self.programs
+            .entry(*program_id)
+            .or_default()
+            .record(pcs, text);
+    }
+
+    /// Merges `other` into `self`, returns the number of edges not seen before.
+    pub fn merge(&mut self, other: &SbfCoverage) -> usize {
+        other
+            .programs
+            .iter()
+            .map(|(program_id, coverage)| {
+                self.programs
+                    .entry(*program_id)
+                    .or_default()
+                    .merge(coverage)
+            })
+            .sum()
+    }
+
+    /// Writes the coverage into an AFL style feedback map.
+    ///
+    /// Every edge is hashed together with its program id into a slot of `map`,
+    /// the slot holds the bucketed hit count of the edge. Slots of edges
+    /// that were not hit are left untouched.
+    pub fn write_feedback_map(&self, map: &mut [u8]) {
+        if map.is_empty() {
+            return;
+        }
+        for (program_id, coverage) in &self.programs {
+            for (edge, hits) in &coverage.edges {
+                let mut hasher = DefaultHasher::new();
+                program_id.hash(&mut hasher);
+                edge.hash(&mut hasher);
+                let slot = (hasher.finish() % map.len() as u64) as usize;
+                map[slot] = map[slot].max(hit_count_bucket(*hits));
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.programs.clear();
+    }
+}
+
+impl ProgramCoverage {
+    /// Number of executions of every covered program counter.
+    pub fn pc_hits(&self) -> &BTreeMap<u64, u64> {
+        &self.pc_hits
+    }
+
+    pub fn executed_pcs(&self) -> impl Iterator<Item = u64> + '_ {
+        self.pc_hits.keys().copied()
+    }
+
+    /// Number of executions of every control flow edge as `(from_pc, to_pc)`,
+    /// the entry of an invocation is recorded with [`ENTRY_EDGE`] as its source.
+    pub fn edges(&self) -> &HashMap<(u64, u64), u64> {
+        &self.edges
+    }
+
+    /// Program counters which start a basic block.
+    pub fn basic_blocks(&self) -> impl Iterator<Item = u64> + '_ {
+        let mut blocks: Vec<u64> = self.edges.keys().map(|(_, to)| *to).collect();
+        blocks.sort_unstable();
+        blocks.dedup();
+        blocks.into_iter()
+    }
+
+    fn record(&mut self, pcs: &[u64], text: &[u8]) {
+        let mut previous = ENTRY_EDGE;
+        for &pc in pcs {
+            *self.pc_hits.entry(pc).or_default() += 1;
+            if previous == ENTRY_EDGE || fall_through(text, previous) != Some(pc) {
+                *self.edges.entry((previous, pc)).or_default() += 1;
+            }
+            previous = pc;
+        }
+    }
+
+    fn merge(&mut self, other: &ProgramCoverage) -> usize {
+        for (pc, hits) in &other.pc_hits {
+            *self.pc_hits.entry(*pc).or_default() += hits;
+        }
+        let mut new_edges = 0;
+        for (edge, hits) in &other.edges {
+            let entry = self.edges.entry(*edge).or_default();
+            if *entry == 0 {
+                new_edges += 1;
+            }
+            *entry += hits;
+        }
+        new_edges
+    }
+}
+
+/// Program counter executed after the instruction at `pc`, `None` for jumps.
+fn fall_through(text: &[u8], pc: u64) -> Option<u64> {
+    let opcode = usize::try_from(pc)
+        .ok()
+        .and_then(|pc| pc.checked_mul(ebpf::INSN_SIZE))
+        .and_then(|offset| text.get(offset))
+        .copied()
+        .unwrap_or_default();
+    match opcode {
+        _ if opcode & ebpf::BPF_CLS_MASK == ebpf::BPF_JMP => None,
+        // the immediate takes a second slot
+        ebpf::LD_DW_IMM => pc.checked_add(2),
+        _ => pc.checked_add(1),
+    }
+}
+
+fn hit_count_bucket(hits: u64) -> u8 {
+    match hits {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3 => 4,
+        4..=7 => 8,
+        8..=15 => 16,
+        16..=31 => 32,
+        32..=127 => 64,
+        _ => 128,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_program_runtime::solana_sbpf::ebpf;
+    use solana_pubkey::Pubkey;
+
+    use super::SbfCoverage;
+    use super::ENTRY_EDGE;
+
+    /// `.text` of `len` instructions, `mov64 r0, 0` except for the given ones.
+    fn text(len: usize, instructions: &[(usize, u8)]) -> Vec<u8> {
+        let mut text = vec![0; len * ebpf::INSN_SIZE];
+        for pc in 0..len {
+            text[pc * ebpf::INSN_SIZE] = ebpf::MOV64_IMM;
+        }
+        for (pc, opcode) in instructions {
+            text[pc * ebpf::INSN_SIZE] = *opcode;
+        }
+        text
+    }
+
+    #[test]
+    fn test_edges_and_basic_blocks() {
+        let program_id = Pubkey::new_unique();
+        let mut coverage = SbfCoverage::default();
+
+        // 0 -> 1 -> 2 calls 5 -> 6, then returns to 3
+        let text = text(7, &[(2, ebpf::CALL_IMM), (6, ebpf::EXIT)]);
+        coverage.record(&program_id, &[0, 1, 2, 5, 6, 3], &text);
+
+        let program = coverage.program(&program_id).unwrap();
+        assert_eq!(
+            program.executed_pcs().collect::<Vec<_>>(),
+            [0, 1, 2, 3, 5, 6]
+        );
+        assert_eq!(program.edges().len(), 3);
+        assert!(program.edges().contains_key(&(ENTRY_EDGE, 0)));
+        assert!(program.edges().contains_key(&(2, 5)));
+        assert!(program.edges().contains_key(&(6, 3)));
+        assert_eq!(program.basic_blocks().collect::<Vec<_>>(), [0, 3, 5]);
+    }
+
+    #[test]
+    fn test_merge_reports_new_edges() {
+        let program_id = Pubkey::new_unique();
+        // 1 branches to 4 or falls through to 2, 0 jumps to 3 on the other path
+        let text = text(5, &[(0, ebpf::JEQ_IMM), (1, ebpf::JEQ_IMM)]);
+        let mut corpus = SbfCoverage::default();
+        corpus.record(&program_id, &[0, 1, 4], &text);
+
+        let mut same_path = SbfCoverage::default();
+        same_path.record(&program_id, &[0, 1, 4], &text);
+        assert_eq!(corpus.merge(&same_path), 0);
+
+        // the branch is not taken
+        let mut fall_through = SbfCoverage::default();
+        fall_through.record(&program_id, &[0, 1, 2], &text);
+        assert_eq!(corpus.merge(&fall_through), 1);
+
+        let mut branch = SbfCoverage::default();
+        branch.record(&program_id, &[0, 3], &text);
+        assert_eq!(corpus.merge(&branch), 1);
+        assert_eq!(corpus.program(&program_id).unwrap().pc_hits()[&0], 4);
+    }
+
+    #[test]
+    fn test_wide_instructions_fall_through() {
+        let program_id = Pubkey::new_unique();
+        let text = text(4, &[(0, ebpf::LD_DW_IMM)]);
+        let mut coverage = SbfCoverage::default();
+        coverage.record(&program_id, &[0, 2, 3], &text);
+
+        let program = coverage.program(&program_id).unwrap();
+        assert_eq!(program.edges().len(), 1);
+        assert_eq!(program.basic_blocks().collect::<Vec<_>>(), [0]);
+    }
+
+    #[test]
+    fn test_feedback_map() {
+        let mut coverage = SbfCoverage::default();
+        let text = text(8, &[(7, ebpf::JA)]);
+        coverage.record(&Pubkey::new_from_array([1; 32]), &[0, 7, 7, 7], &text);
+
+        let mut map = vec![0u8; 1 << 16];
+        coverage.write_feedback_map(&mut map);
+        let mut hit_counts: Vec<u8> = map.iter().copied().filter(|slot| *slot != 0).collect();
+        hit_counts.sort_unstable();
+
+        // entry edge hit once, 0 -> 7 once and the 7 -> 7 loop twice
+        assert_eq!(hit_counts, [1, 1, 2]);
+    }
+}
@@ -0,0 +1,466 @@
+use solana_pubkey::Pubkey;
+
+use crate::types::account_reader::Reader;
+use crate::types::bundled_program::BundledProgram;
+
+const MINT_LEN: usize = 82;
+const ACCOUNT_LEN: usize = 165;
+/// Token 2022 refuses accounts of the multisig size, they are padded instead.
+const MULTISIG_LEN: usize = 355;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+/// Mint of SPL Token or Token 2022, extensions are supported only by Token 2022.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TokenMint {
+    pub mint_authority: Option<Pubkey>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub freeze_authority: Option<Pubkey>,
+    pub extensions: Vec<MintExtension>,
+}
+
+/// Token account of SPL Token or Token 2022, extensions are supported only by Token 2022.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub delegate: Option<Pubkey>,
+    pub is_frozen: bool,
+    /// Rent exempt reserve of wrapped SOL accounts
+    pub is_native: Option<u64>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<Pubkey>,
+    pub extensions: Vec<AccountExtension>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MintExtension {
+    /// The same fee is used for the older and the newer epoch.
+    TransferFeeConfig {
+        transfer_fee_config_authority: Option<Pubkey>,
+        withdraw_withheld_authority: Option<Pubkey>,
+        withheld_amount: u64,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    },
+    MintCloseAuthority {
+        close_authority: Option<Pubkey>,
+    },
+    ConfidentialTransferMint {
+        authority: Option<Pubkey>,
+        auto_approve_new_accounts: bool,
+        auditor_elgamal_pubkey: Option<[u8; 32]>,
+    },
+    NonTransferable,
+    /// Rates are in basis points, timestamps are unix timestamps.
+    InterestBearingConfig {
+        rate_authority: Option<Pubkey>,
+        initialization_timestamp: i64,
+        pre_update_average_rate: i16,
+        last_update_timestamp: i64,
+        current_rate: i16,
+    },
+    PermanentDelegate {
+        delegate: Option<Pubkey>,
+    },
+    TransferHook {
+        authority: Option<Pubkey>,
+        program_id: Option<Pubkey>,
+    },
+    /// Extension without a typed representation, kept as raw TLV data.
+    Other {
+        extension_type: u16,
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccountExtension {
+    TransferFeeAmount {
+        withheld_amount: u64,
+    },
+    ImmutableOwner,
+    MemoTransfer {
+        require_incoming_transfer_memos: bool,
+    },
+    CpiGuard {
+        lock_cpi: bool,
+    },
+    NonTransferableAccount,
+    TransferHookAccount {
+        transferring: bool,
+    },
+    /// Extension without a typed representation, kept as raw TLV data.
+    Other {
+        extension_type: u16,
+        data: Vec<u8>,
+    },
+}
+
+impl MintExtension {
+    fn extension_type(&self) -> u16 {
+        match self {
+            MintExtension::TransferFeeConfig { .. } => 1,
+            MintExtension::MintCloseAuthority { .. } => 3,
+            MintExtension::ConfidentialTransferMint { .. } => 4,
+            MintExtension::NonTransferable => 9,
+            MintExtension::InterestBearingConfig { .. } => 10,
+            MintExtension::PermanentDelegate { .. } => 12,
+            MintExtension::TransferHook { .. } => 14,
+            MintExtension::Other { extension_type, .. } => *extension_type,
+        }
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        match self {
+            MintExtension::TransferFeeConfig {
+                transfer_fee_config_authority,
+                withdraw_withheld_authority,
+                withheld_amount,
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => {
+                push_optional_pubkey(&mut data, transfer_fee_config_authority);
+                push_optional_pubkey(&mut data, withdraw_withheld_authority);
+                data.extend_from_slice(&withheld_amount.to_le_bytes());
+                // older and newer transfer fee, both valid since epoch 0
+                for _ in 0..2 {
+                    data.extend_from_slice(&0u64.to_le_bytes());
+                    data.extend_from_slice(&maximum_fee.to_le_bytes());
+                    data.extend_from_slice(&transfer_fee_basis_points.to_le_bytes());
+                }
+            }
+            MintExtension::MintCloseAuthority { close_authority } => {
+                push_optional_pubkey(&mut data, close_authority)
+            }
+            MintExtension::ConfidentialTransferMint {
+                authority,
+                auto_approve_new_accounts,
+                auditor_elgamal_pubkey,
+            } => {
+                push_optional_pubkey(&mut data, authority);
+                data.push(*auto_approve_new_accounts as u8);
+                data.extend_from_slice(&auditor_elgamal_pubkey.unwrap_or_default());
+            }
+            MintExtension::NonTransferable => {}
+            MintExtension::InterestBearingConfig {
+                rate_authority,
+                initialization_timestamp,
+                pre_update_average_rate,
+                last_update_timestamp,
+                current_rate,
+            } => {
+                push_optional_pubkey(&mut data, rate_authority);
+                data.extend_from_slice(&initialization_timestamp.to_le_bytes());
+                data.extend_from_slice(&pre_update_average_rate.to_le_bytes());
+                data.extend_from_slice(&last_update_timestamp.to_le_bytes());
+                data.extend_from_slice(&current_rate.to_le_bytes());
+            }
+            MintExtension::PermanentDelegate { delegate } => {
+                push_optional_pubkey(&mut data, delegate)
+            }
+            MintExtension::TransferHook {
+                authority,
+                program_id,
+            } => {
+                push_optional_pubkey(&mut data, authority);
+                push_optional_pubkey(&mut data, program_id);
+            }
+            MintExtension::Other { data: value, .. } => data.extend_from_slice(value),
+        }
+        data
+    }
+
+    fn deserialize(extension_type: u16, data: &[u8]) -> Option<Self> {
+        let mut reader = Reader(data);
+        let extension = match extension_type {
+            1 => {
+                let transfer_fee_config_authority = reader.optional_pubkey()?;
+                let withdraw_withheld_authority = reader.optional_pubkey()?;
+                let withheld_amount = reader.u64()?;
+                // skip the older transfer fee and the epoch of the newer one
+                reader.bytes(26)?;
+                let maximum_fee = reader.u64()?;
+                let transfer_fee_basis_points = u16::from_le_bytes(reader.array()?);
+                MintExtension::TransferFeeConfig {
+                    transfer_fee_config_authority,
+                    withdraw_withheld_authority,
+                    withheld_amount,
+                    transfer_fee_basis_points,
+                    maximum_fee,
+                }
+            }
+            3 => MintExtension::MintCloseAuthority {
+                close_authority: reader.optional_pubkey()?,
+            },
+            4 => MintExtension::ConfidentialTransferMint {
+                authority: reader.optional_pubkey()?,
+                auto_approve_new_accounts: reader.u8()? != 0,
+                auditor_elgamal_pubkey: Some(reader.array()?).filter(|key| *key != [0; 32]),
+            },
+            9 => MintExtension::NonTransferable,
+            10 => MintExtension::InterestBearingConfig {
+                rate_authority: reader.optional_pubkey()?,
+                initialization_timestamp: i64::from_le_bytes(reader.array()?),
+                pre_update_average_rate: i16::from_le_bytes(reader.array()?),
+                last_update_timestamp: i64::from_le_bytes(reader.array()?),
+                current_rate: i16::from_le_bytes(reader.array()?),
+            },
+            12 => MintExtension::PermanentDelegate {
+                delegate: reader.optional_pubkey()?,
+            },
+            14 => MintExtension::TransferHook {
+                authority: reader.optional_pubkey()?,
+                program_id: reader.optional_pubkey()?,
+            },
+            _ => MintExtension::Other {
+                extension_type,
+                data: data.to_vec(),
+            },
+        };
+        Some(extension)
+    }
+
+    /// Account extension Token 2022 requires on every account of a mint with this extension.
+    pub(crate) fn required_account_extension(&self) -> Option<AccountExtension> {
+        match self {
+            MintExtension::TransferFeeConfig { .. } => {
+                Some(AccountExtension::TransferFeeAmount { withheld_amount: 0 })
+            }
+            MintExtension::NonTransferable => Some(AccountExtension::NonTransferableAccount),
+            MintExtension::TransferHook { .. } => Some(AccountExtension::TransferHookAccount {
+                transferring: false,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl AccountExtension {
+    fn extension_type(&self) -> u16 {
+        match self {
+            AccountExtension::TransferFeeAmount { .. } => 2,
+            AccountExtension::ImmutableOwner => 7,
+            AccountExtension::MemoTransfer { .. } => 8,
+            AccountExtension::CpiGuard { .. } => 11,
+            AccountExtension::NonTransferableAccount => 13,
+            AccountExtension::TransferHookAccount { .. } => 15,
+            AccountExtension::Other { extension_type, .. } => *extension_type,
+        }
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        match self {
+            AccountExtension::TransferFeeAmount { withheld_amount } => {
+                withheld_amount.to_le_bytes().to_vec()
+            }
+            AccountExtension::ImmutableOwner | AccountExtension::NonTransferableAccount => vec![],
+            AccountExtension::MemoTransfer {
+                require_incoming_transfer_memos: flag,
+            }
+            | AccountExtension::CpiGuard { lock_cpi: flag }
+            | AccountExtension::TransferHookAccount { transferring: flag } => vec![*flag as u8],
+            AccountExtension::Other { data, .. } => data.clone(),
+        }
+    }
+
+    fn deserialize(extension_type: u16, data: &[u8]) -> Option<Self> {
+        let mut reader = Reader(data);
+        let extension = match extension_type {
+            2 => AccountExtension::TransferFeeAmount {
+                withheld_amount: reader.u64()?,
+            },
+            7 => AccountExtension::ImmutableOwner,
+            8 => AccountExtension::MemoTransfer {
+                require_incoming_transfer_memos: reader.u8()? != 0,
+            },
+            11 => AccountExtension::CpiGuard {
+                lock_cpi: reader.u8()? != 0,
+            },
+            13 => AccountExtension::NonTransferableAccount,
+            15 => AccountExtension::TransferHookAccount {
+                transferring: reader.u8()? != 0,
+            },
+            _ => AccountExtension::Other {
+                extension_type,
+                data: data.to_vec(),
+            },
+        };
+        Some(extension)
+    }
+}
+
+impl TokenMint {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(MINT_LEN);
+        push_coption_pubkey(&mut data, &self.mint_authority);
+        data.extend_from_slice(&self.supply.to_le_bytes());
+        data.push(self.decimals);
+        // is initialized
+        data.push(1);
+        push_coption_pubkey(&mut data, &self.freeze_authority);
+
+        let extensions: Vec<_> = self
+            .extensions
+            .iter()
+            .map(|extension| (extension.extension_type(), extension.serialize()))
+            .collect();
+        push_extensions(&mut data, ACCOUNT_TYPE_MINT, &extensions);
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader(data.get(..MINT_LEN)?);
+        let mint_authority = reader.coption_pubkey()?;
+        let supply = reader.u64()?;
+        let decimals = reader.u8()?;
+        if reader.u8()? != 1 {
+            return None;
+        }
+        let freeze_authority = reader.coption_pubkey()?;
+        let extensions = read_extensions(data, ACCOUNT_TYPE_MINT)?
+            .into_iter()
+            .map(|(extension_type, value)| MintExtension::deserialize(extension_type, value))
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            mint_authority,
+            supply,
+            decimals,
+            freeze_authority,
+            extensions,
+        })
+    }
+}
+
+impl TokenAccount {
+    /// Address of the associated token account of the owner.
+    pub fn associated_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+            &BundledProgram::AssociatedToken.program_id(),
+        )
+        .0
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(ACCOUNT_LEN);
+        data.extend_from_slice(self.mint.as_ref());
+        data.extend_from_slice(self.owner.as_ref());
+        data.extend_from_slice(&self.amount.to_le_bytes());
+        push_coption_pubkey(&mut data, &self.delegate);
+        data.push(if self.is_frozen { 2 } else { 1 });
+        match self.is_native {
+            Some(reserve) => {
+                data.extend_from_slice(&1u32.to_le_bytes());
+                data.extend_from_slice(&reserve.to_le_bytes());
+            }
+            None => data.extend_from_slice(&[0; 12]),
+        }
+        data.extend_from_slice(&self.delegated_amount.to_le_bytes());
+        push_coption_pubkey(&mut data, &self.close_authority);
+
+        let extensions: Vec<_> = self
+            .extensions
+            .iter()
+            .map(|extension| (extension.extension_type(), extension.serialize()))
+            .collect();
+        push_extensions(&mut data, ACCOUNT_TYPE_ACCOUNT, &extensions);
+        data
+    }
+
+    pub fn deserialize(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader(data.get(..ACCOUNT_LEN)?);
+        let mint = reader.pubkey()?;
+        let owner = reader.pubkey()?;
+        let amount = reader.u64()?;
+        let delegate = reader.coption_pubkey()?;
+        let is_frozen = match reader.u8()? {
+            1 => false,
+            2 => true,
+            _ => return None,
+        };
+        let is_native = match u32::from_le_bytes(reader.array()?) {
+            0 => {
+                reader.u64()?;
+                None
+            }
+            _ => Some(reader.u64()?),
+        };
+        let delegated_amount = reader.u64()?;
+        let close_authority = reader.coption_pubkey()?;
+        let extensions = read_extensions(data, ACCOUNT_TYPE_ACCOUNT)?
+            .into_iter()
+            .map(|(extension_type, value)| AccountExtension::deserialize(extension_type, value))
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            mint,
+            owner,
+            amount,
+            delegate,
+            is_frozen,
+            is_native,
+            delegated_amount,
+            close_authority,
+            extensions,
+        })
+    }
+}
+
+/// Appends the account type and the TLV encoded extensions after the base state
+/// padded to the size of a token account.
+fn push_extensions(data: &mut Vec<u8>, account_type: u8, extensions: &[(u16, Vec<u8>)]) {
+    if extensions.is_empty() {
+        return;
+    }
+    data.resize(ACCOUNT_LEN, 0);
+    data.push(account_type);
+    for (extension_type, value) in extensions {
+        data.extend_from_slice(&extension_type.to_le_bytes());
+        data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        data.extend_from_slice(value);
+    }
+    if data.len() == MULTISIG_LEN {
+        // uninitialized extension type
+        data.extend_from_slice(&[0; 2]);
+    }
+}
+
+fn read_extensions(data: &[u8], account_type: u8) -> Option<Vec<(u16, &[u8])>> {
+    let mut extensions = Vec::new();
+    if data.len() <= ACCOUNT_LEN {
+        return Some(extensions);
+    }
+    if data[ACCOUNT_LEN] != account_type {
+        return None;
+    }
+    let mut reader = Reader(&data[ACCOUNT_LEN + 1..]);
+    while reader.0.len() >= 4 {
+        let extension_type = u16::from_le_bytes(reader.array()?);
+        let length = u16::from_le_bytes(reader.array()?);
+        if extension_type == 0 {
+            break;
+        }
+        extensions.push((extension_type, reader.bytes(length as usize)?));
+    }
+    Some(extensions)
+}
+
+fn push_coption_pubkey(data: &mut Vec<u8>, pubkey: &Option<Pubkey>) {
+    match pubkey {
+        Some(pubkey) => {
+            data.extend_from_slice(&1u32.to_le_bytes());
+            data.extend_from_slice(pubkey.as_ref());
+        }
+        None => data.extend_from_slice(&[0; 36]),
+    }
+}
+
+/// Token 2022 extensions store `None` as the default pubkey.
+fn push_optional_pubkey(data: &mut Vec<u8>, pubkey: &Option<Pubkey>) {
+    data.extend_from_slice(pubkey.unwrap_or_default().as_ref());
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+use solana_clock::Clock;
+use solana_hash::Hash;
+use solana_transaction::Transaction;
+use solana_transaction_error::TransactionError;
+
+use crate::types::execution_mode::ExecutionMode;
+use crate::types::fault_injection::FaultInjection;
+
+/// Version of the recording file format, written at the start of every file.
+const FORMAT_VERSION: u32 = 2;
+
+/// Transactions processed by a [`crate::trident_svm::TridentSVM`] in order, which can be
+/// replayed on an instance built from the same initial state, see
+/// [`crate::trident_svm::TridentSVM::replay_transactions`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransactionRecording {
+    transactions: Vec<RecordedTransaction>,
+}
+
+/// A processed transaction and the outcome it had.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedTransaction {
+    pub transaction: Transaction,
+    /// The clock the transaction was executed at, including the slot.
+    pub clock: Clock,
+    /// Whether the changes of the transaction were stored, as by `process_transaction_with_settle`.
+    pub settled: bool,
+    /// Faults injected into the transaction, injected again when it is replayed.
+    pub fault_injection: FaultInjection,
+    /// Mode the instance executed SBF programs in, which does not change the outcome.
+    pub execution_mode: ExecutionMode,
+    pub result: Result<(), TransactionError>,
+    /// Hash of the writable accounts after the execution, `None` if the transaction
+    /// was not executed.
+    pub accounts_hash: Option<Hash>,
+}
+
+/// The first replayed transaction whose result or writable accounts differ from the recorded ones.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayMismatch {
+    /// Index of the transaction in the recording.
+    pub index: usize,
+    pub recorded: Result<(), TransactionError>,
+    pub replayed: Result<(), TransactionError>,
+    /// Whether the writable accounts match the recorded ones, see [`RecordedTransaction::accounts_hash`].
+    pub accounts_match: bool,
+}
+
+impl TransactionRecording {
+    pub fn transactions(&self) -> &[RecordedTransaction] {
+        &self.transactions
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    pub(crate) fn record(&mut self, transaction: RecordedTransaction) {
+        self.transactions.push(transaction);
+    }
+
+    /// Writes the recording in the compact binary format read by [`TransactionRecording::read`].
+    pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        bincode::serialize_into(&mut *writer, &FORMAT_VERSION)
+            .and_then(|_| bincode::serialize_into(writer, self))
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> std::io::Result<Self> {
+        let version: u32 = bincode::deserialize_from(&mut *reader)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        if version != FORMAT_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unsupported transaction recording version {version}"),
+            ));
+        }
+        bincode::deserialize_from(reader)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::read(&mut reader)
+    }
+}
+
+impl fmt::Display for ReplayMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.recorded != self.replayed {
+            write!(
+                f,
+                "Transaction {} was recorded with {:?}, but replayed with {:?}",
+                self.index, self.recorded, self.replayed
+            )
+        } else {
+            write!(
+                f,
+                "Transaction {} changed the writable accounts differently than recorded",
+                self.index
+            )
+        }
+    }
+}
+
+impl std::error::Error for ReplayMismatch {}
@@ -1,8 +1,14 @@
+use std::collections::HashMap;
+
+use solana_pubkey::Pubkey;
 use solana_svm::transaction_processor::LoadAndExecuteSanitizedTransactionsOutput;
 
+use crate::types::execution_mode::ExecutionMode;
+
 pub struct TridentTransactionProcessingResult {
     result: LoadAndExecuteSanitizedTransactionsOutput,
     transaction_timestamp: u64,
+    execution_modes: HashMap<Pubkey, ExecutionMode>,
 }
 
 impl TridentTransactionProcessingResult {
@@ -13,9 +19,18 @@ impl TridentTransactionProcessingResult {
         Self {
             result,
             transaction_timestamp,
+            execution_modes: HashMap::new(),
         }
     }
 
+    pub(crate) fn with_execution_modes(
+        mut self,
+        execution_modes: HashMap<Pubkey, ExecutionMode>,
+    ) -> Self {
+        self.execution_modes = execution_modes;
+        self
+    }
+
     pub fn get_result(&self) -> &LoadAndExecuteSanitizedTransactionsOutput {
         &self.result
     }
@@ -23,4 +38,11 @@ impl TridentTransactionProcessingResult {
     pub fn get_transaction_timestamp(&self) -> u64 {
         self.transaction_timestamp
     }
+
+    /// Returns the mode the SBF program was executed in, both for programs invoked by the
+    /// instructions of the transaction and through CPI. `None` for builtins, programs which
+    /// failed to load and accounts which are not programs.
+    pub fn get_execution_mode(&self, program_id: &Pubkey) -> Option<ExecutionMode> {
+        self.execution_modes.get(program_id).copied()
+    }
 }
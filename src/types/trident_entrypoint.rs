@@ -1,11 +1,24 @@
+use solana_program_error::ProgramResult;
 use solana_program_runtime::invoke_context::BuiltinFunctionWithContext;
 
 use solana_pubkey::Pubkey;
 
+use crate::processor::account_info::AccountInfo;
+
+/// The `process_instruction` function of a natively compiled program.
+pub type NativeEntrypoint = fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult;
+
+#[derive(Clone, Copy)]
+pub(crate) enum ProgramEntry {
+    /// A builtin function, such as one created by the `processor!` macro.
+    Builtin(BuiltinFunctionWithContext),
+    Native(NativeEntrypoint),
+}
+
 pub struct TridentEntrypoint {
     pub(crate) program_id: Pubkey,
     pub(crate) authority: Option<Pubkey>,
-    pub(crate) entry: Option<BuiltinFunctionWithContext>,
+    pub(crate) entry: Option<ProgramEntry>,
 }
 impl TridentEntrypoint {
     pub fn new(
@@ -16,7 +29,24 @@ impl TridentEntrypoint {
         Self {
             program_id,
             authority,
-            entry: entry_fn,
+            entry: entry_fn.map(ProgramEntry::Builtin),
+        }
+    }
+
+    /// Creates a native program from its `process_instruction` function.
+    ///
+    /// The accounts and instruction data are serialized the same way as for SBF programs,
+    /// CPIs and return data go through the syscall stubs, and panics fail the instruction
+    /// with `ProgramFailedToComplete`.
+    pub fn native(
+        program_id: Pubkey,
+        authority: Option<Pubkey>,
+        entry: NativeEntrypoint,
+    ) -> TridentEntrypoint {
+        Self {
+            program_id,
+            authority,
+            entry: Some(ProgramEntry::Native(entry)),
         }
     }
 }
@@ -1,9 +1,50 @@
+use std::collections::HashMap;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::str::FromStr;
+
+use solana_keypair::read_keypair_file;
 use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+
+/// Loader which owns a deployed SBF program.
+///
+/// The loader determines how the program is stored in accounts and which input layout
+/// the program receives, programs of `bpf_loader_deprecated` use the unaligned layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProgramLoader {
+    /// `BPFLoaderUpgradeab1e11111111111111111111111`, the program data is stored in a
+    /// separate ProgramData account.
+    #[default]
+    Upgradeable,
+    /// `BPFLoader2111111111111111111111111111111111`, the program account holds the ELF
+    /// and cannot be upgraded.
+    NonUpgradeable,
+    /// `BPFLoader1111111111111111111111111111111111`, like [`ProgramLoader::NonUpgradeable`]
+    /// but with the deprecated unaligned input layout.
+    Deprecated,
+    /// `LoaderV411111111111111111111111111111111111`, the program account holds the loader
+    /// state followed by the ELF. Programs without an authority are deployed as finalized.
+    V4,
+}
+
+impl ProgramLoader {
+    pub fn id(&self) -> Pubkey {
+        match self {
+            ProgramLoader::Upgradeable => solana_sdk_ids::bpf_loader_upgradeable::id(),
+            ProgramLoader::NonUpgradeable => solana_sdk_ids::bpf_loader::id(),
+            ProgramLoader::Deprecated => solana_sdk_ids::bpf_loader_deprecated::id(),
+            ProgramLoader::V4 => solana_sdk_ids::loader_v4::id(),
+        }
+    }
+}
 
 pub struct TridentProgram {
     pub(crate) program_id: Pubkey,
     pub(crate) authority: Option<Pubkey>,
     pub(crate) data: Vec<u8>,
+    pub(crate) loader: ProgramLoader,
 }
 impl TridentProgram {
     pub fn new(program_id: Pubkey, authority: Option<Pubkey>, data: Vec<u8>) -> Self {
@@ -11,6 +52,203 @@ impl TridentProgram {
             program_id,
             authority,
             data,
+            loader: ProgramLoader::default(),
         }
     }
+
+    /// Reads the program from a `.so` file, the program id is taken from the
+    /// `<name>-keypair.json` file next to it, as written by `cargo build-sbf`.
+    ///
+    /// Programs without a keypair are looked up in the `[programs]` table of the
+    /// closest `Anchor.toml` in the directory of the file or its parents.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let program_id = match read_program_keypair_id(path)? {
+            Some(program_id) => program_id,
+            None => {
+                let dir = path
+                    .parent()
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."));
+                anchor_program_id(&read_anchor_programs(dir)?, path)?
+            }
+        };
+        Ok(Self::new(program_id, None, std::fs::read(path)?))
+    }
+
+    /// Reads all `.so` files of a directory such as `target/deploy`.
+    ///
+    /// Program ids are taken from the `<name>-keypair.json` files, programs without
+    /// a keypair are looked up in the `[programs]` table of the closest `Anchor.toml`
+    /// in the directory or its parents. Programs are sorted by file name.
+    pub fn from_dir<P: AsRef<Path>>(path: P) -> std::io::Result<Vec<Self>> {
+        let path = path.as_ref();
+        let mut binaries = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            let binary = entry?.path();
+            if binary
+                .extension()
+                .is_some_and(|extension| extension == "so")
+            {
+                binaries.push(binary);
+            }
+        }
+        binaries.sort();
+
+        let mut anchor_programs = None;
+        let mut programs = Vec::with_capacity(binaries.len());
+        for binary in binaries {
+            let program_id = match read_program_keypair_id(&binary)? {
+                Some(program_id) => program_id,
+                None => {
+                    let anchor_programs = match &anchor_programs {
+                        Some(anchor_programs) => anchor_programs,
+                        None => anchor_programs.insert(read_anchor_programs(path)?),
+                    };
+                    anchor_program_id(anchor_programs, &binary)?
+                }
+            };
+            programs.push(Self::new(program_id, None, std::fs::read(&binary)?));
+        }
+        Ok(programs)
+    }
+
+    /// Deploys the program under the given loader instead of the BPF Loader Upgradeable.
+    pub fn with_loader(mut self, loader: ProgramLoader) -> Self {
+        self.loader = loader;
+        self
+    }
+}
+
+fn program_name(binary: &Path) -> std::io::Result<&str> {
+    binary
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid program file name {}", binary.display()),
+            )
+        })
+}
+
+/// Reads the id of the program from the `<name>-keypair.json` file next to its binary.
+fn read_program_keypair_id(binary: &Path) -> std::io::Result<Option<Pubkey>> {
+    let keypair_path = binary.with_file_name(format!("{}-keypair.json", program_name(binary)?));
+    if !keypair_path.exists() {
+        return Ok(None);
+    }
+    let keypair = read_keypair_file(&keypair_path)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    Ok(Some(keypair.pubkey()))
+}
+
+/// Returns the id of the program in the `Anchor.toml` programs, see [`read_anchor_programs`].
+fn anchor_program_id(
+    anchor_programs: &HashMap<String, Pubkey>,
+    binary: &Path,
+) -> std::io::Result<Pubkey> {
+    anchor_programs
+        .get(program_name(binary)?)
+        .copied()
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("No program id found for {}", binary.display()),
+            )
+        })
+}
+
+/// Reads program ids from the `[programs.<cluster>]` tables of the closest `Anchor.toml`,
+/// ids of the `localnet` cluster take precedence.
+fn read_anchor_programs(dir: &Path) -> std::io::Result<HashMap<String, Pubkey>> {
+    let Some(anchor_toml) = dir
+        .ancestors()
+        .map(|ancestor| ancestor.join("Anchor.toml"))
+        .find(|anchor_toml| anchor_toml.exists())
+    else {
+        return Ok(HashMap::new());
+    };
+
+    let config: toml::Table = std::fs::read_to_string(&anchor_toml)?
+        .parse()
+        .map_err(|err: toml::de::Error| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+    let Some(clusters) = config
+        .get("programs")
+        .and_then(|programs| programs.as_table())
+    else {
+        return Ok(HashMap::new());
+    };
+
+    let mut clusters: Vec<_> = clusters.iter().collect();
+    // the last inserted id wins, so localnet is visited last
+    clusters.sort_by_key(|(cluster, _)| (cluster.as_str() == "localnet", cluster.as_str()));
+
+    let mut programs = HashMap::new();
+    for (_cluster, cluster_programs) in clusters {
+        let Some(cluster_programs) = cluster_programs.as_table() else {
+            continue;
+        };
+        for (name, program) in cluster_programs {
+            // programs are either `name = "<id>"` or `name = { address = "<id>", ... }`
+            let address = program
+                .as_str()
+                .or_else(|| program.get("address").and_then(|address| address.as_str()));
+            if let Some(program_id) = address.and_then(|address| Pubkey::from_str(address).ok()) {
+                programs.insert(name.clone(), program_id);
+            }
+        }
+    }
+    Ok(programs)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_keypair::write_keypair_file;
+    use solana_keypair::Keypair;
+    use solana_pubkey::Pubkey;
+    use solana_signer::Signer;
+
+    use super::TridentProgram;
+
+    #[test]
+    fn test_programs_from_dir() {
+        let project = std::env::temp_dir().join(format!("trident-svm-{}", Pubkey::new_unique()));
+        let deploy = project.join("target").join("deploy");
+        std::fs::create_dir_all(&deploy).unwrap();
+
+        let keypair = Keypair::new();
+        write_keypair_file(&keypair, deploy.join("with_keypair-keypair.json")).unwrap();
+        std::fs::write(deploy.join("with_keypair.so"), [1]).unwrap();
+
+        let devnet_id = Pubkey::new_unique();
+        let localnet_id = Pubkey::new_unique();
+        std::fs::write(
+            project.join("Anchor.toml"),
+            format!(
+                "[programs.devnet]\nfrom_anchor = \"{devnet_id}\"\n\n\
+                 [programs.localnet]\nfrom_anchor = {{ address = \"{localnet_id}\" }}\n"
+            ),
+        )
+        .unwrap();
+        std::fs::write(deploy.join("from_anchor.so"), [2]).unwrap();
+
+        let programs = TridentProgram::from_dir(&deploy).unwrap();
+        assert_eq!(programs.len(), 2);
+        assert_eq!(programs[0].program_id, localnet_id);
+        assert_eq!(programs[0].data, [2]);
+        assert_eq!(programs[1].program_id, keypair.pubkey());
+        assert_eq!(programs[1].data, [1]);
+
+        let program = TridentProgram::from_file(deploy.join("with_keypair.so")).unwrap();
+        assert_eq!(program.program_id, keypair.pubkey());
+        let program = TridentProgram::from_file(deploy.join("from_anchor.so")).unwrap();
+        assert_eq!(program.program_id, localnet_id);
+
+        std::fs::write(deploy.join("unknown.so"), [3]).unwrap();
+        assert!(TridentProgram::from_file(deploy.join("unknown.so")).is_err());
+        assert!(TridentProgram::from_dir(&deploy).is_err());
+
+        std::fs::remove_dir_all(project).unwrap();
+    }
 }